@@ -0,0 +1,200 @@
+use proc_macro::TokenStream;
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse_macro_input, Attribute, Data, DeriveInput, Fields, Lit, Meta, NestedMeta};
+
+mod sql;
+
+#[proc_macro_derive(FromRecord, attributes(record))]
+pub fn derive_from_record(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+fn expand(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(syn::Error::new_spanned(name, "FromRecord requires a struct with named fields")),
+        },
+        _ => return Err(syn::Error::new_spanned(name, "FromRecord can only be derived for structs")),
+    };
+
+    let mut extractions = Vec::with_capacity(fields.len());
+    for field in fields {
+        let ident = field.ident.as_ref().expect("named field");
+        let key = rename(&field.attrs, "record")?.unwrap_or_else(|| ident.to_string().trim_start_matches("r#").to_string());
+        extractions.push(quote! {
+            #ident: record.take(#key)?,
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::surrealdb_util::FromRecord for #name #ty_generics #where_clause {
+            fn from_record(mut record: ::surrealdb_util::Record) -> ::std::result::Result<Self, ::surrealdb_util::Error> {
+                Ok(Self {
+                    #(#extractions)*
+                })
+            }
+        }
+
+        impl #impl_generics ::surrealdb_util::FromValue for #name #ty_generics #where_clause {
+            fn from_value(value: ::surrealdb_util::surrealdb::sql::Value) -> ::std::result::Result<Self, ::surrealdb_util::Error> {
+                <Self as ::surrealdb_util::FromRecord>::from_record(::surrealdb_util::ValueCast::cast(value)?)
+            }
+        }
+    })
+}
+
+/// `sql!(db, "SELECT * FROM user WHERE age > $min", min = 18)` is
+/// `db.query("SELECT * FROM user WHERE age > $min").arg("min", 18)`, with the SQL parsed at
+/// compile time. Every `$param` must be bound, set with LET or provided by the datastore
+/// (such as `$auth`), and every binding used. A binding written as just `min` passes the
+/// variable of that name.
+#[proc_macro]
+pub fn sql(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as sql::SqlInput);
+    sql::expand(input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+#[proc_macro_derive(ValueEnum, attributes(value))]
+pub fn derive_value_enum(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand_value_enum(input).unwrap_or_else(|e| e.to_compile_error()).into()
+}
+
+fn expand_value_enum(input: DeriveInput) -> syn::Result<TokenStream2> {
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    let variants = match &input.data {
+        Data::Enum(data) => &data.variants,
+        _ => return Err(syn::Error::new_spanned(name, "ValueEnum can only be derived for enums")),
+    };
+    let case = rename_all(&input.attrs)?;
+
+    let mut from_arms = Vec::with_capacity(variants.len());
+    let mut into_arms = Vec::with_capacity(variants.len());
+    for variant in variants {
+        if !matches!(variant.fields, Fields::Unit) {
+            return Err(syn::Error::new_spanned(variant, "ValueEnum variants cannot have fields"));
+        }
+        let ident = &variant.ident;
+        let key = match rename(&variant.attrs, "value")? {
+            Some(key) => key,
+            None => case.apply(ident.to_string().trim_start_matches("r#")),
+        };
+        from_arms.push(quote! {
+            #key => Ok(Self::#ident),
+        });
+        into_arms.push(quote! {
+            Self::#ident => #key,
+        });
+    }
+
+    Ok(quote! {
+        impl #impl_generics ::surrealdb_util::FromValue for #name #ty_generics #where_clause {
+            fn from_value(value: ::surrealdb_util::surrealdb::sql::Value) -> ::std::result::Result<Self, ::surrealdb_util::Error> {
+                match &value {
+                    ::surrealdb_util::surrealdb::sql::Value::Strand(s) => match s.as_str() {
+                        #(#from_arms)*
+                        _ => Err(::surrealdb_util::Error::cast_failed::<Self>(&value)),
+                    },
+                    _ => Err(::surrealdb_util::Error::cast_failed::<Self>(&value)),
+                }
+            }
+        }
+
+        impl #impl_generics ::surrealdb_util::IntoValue for #name #ty_generics #where_clause {
+            fn into_value(self) -> ::surrealdb_util::surrealdb::sql::Value {
+                ::surrealdb_util::surrealdb::sql::Value::from(match self {
+                    #(#into_arms)*
+                })
+            }
+        }
+    })
+}
+
+enum Case {
+    Unchanged,
+    Lower,
+    Upper,
+    Snake,
+    ScreamingSnake,
+}
+
+impl Case {
+    fn apply(&self, name: &str) -> String {
+        match self {
+            Case::Unchanged => name.to_string(),
+            Case::Lower => name.to_lowercase(),
+            Case::Upper => name.to_uppercase(),
+            Case::Snake => snake_case(name),
+            Case::ScreamingSnake => snake_case(name).to_uppercase(),
+        }
+    }
+}
+
+fn snake_case(name: &str) -> String {
+    let mut out = String::with_capacity(name.len() + 4);
+    for (i, c) in name.chars().enumerate() {
+        if c.is_uppercase() && i > 0 {
+            out.push('_');
+        }
+        out.extend(c.to_lowercase());
+    }
+    out
+}
+
+fn rename_all(attrs: &[Attribute]) -> syn::Result<Case> {
+    let mut case = Case::Unchanged;
+    for nv in name_values(attrs, "value")? {
+        if !nv.path.is_ident("rename_all") {
+            return Err(syn::Error::new_spanned(nv, "unknown value attribute"));
+        }
+        case = match &nv.lit {
+            Lit::Str(s) => match s.value().as_str() {
+                "lowercase" => Case::Lower,
+                "UPPERCASE" => Case::Upper,
+                "snake_case" => Case::Snake,
+                "SCREAMING_SNAKE_CASE" => Case::ScreamingSnake,
+                _ => return Err(syn::Error::new_spanned(s, "expected \"lowercase\", \"UPPERCASE\", \"snake_case\" or \"SCREAMING_SNAKE_CASE\"")),
+            },
+            lit => return Err(syn::Error::new_spanned(lit, "expected a string literal")),
+        };
+    }
+    Ok(case)
+}
+
+/// The `rename = "..."` value of the `#[<attr>(...)]` attributes, if any.
+fn rename(attrs: &[Attribute], attr: &str) -> syn::Result<Option<String>> {
+    let mut key = None;
+    for nv in name_values(attrs, attr)? {
+        if !nv.path.is_ident("rename") {
+            return Err(syn::Error::new_spanned(nv, format!("unknown {attr} attribute")));
+        }
+        match nv.lit {
+            Lit::Str(s) => key = Some(s.value()),
+            lit => return Err(syn::Error::new_spanned(lit, "expected a string literal")),
+        }
+    }
+    Ok(key)
+}
+
+fn name_values(attrs: &[Attribute], attr: &str) -> syn::Result<Vec<syn::MetaNameValue>> {
+    let mut out = Vec::new();
+    for a in attrs.iter().filter(|a| a.path.is_ident(attr)) {
+        let list = match a.parse_meta()? {
+            Meta::List(list) => list,
+            meta => return Err(syn::Error::new_spanned(meta, format!("expected #[{attr}(...)]"))),
+        };
+        for nested in list.nested {
+            match nested {
+                NestedMeta::Meta(Meta::NameValue(nv)) => out.push(nv),
+                other => return Err(syn::Error::new_spanned(other, format!("unknown {attr} attribute"))),
+            }
+        }
+    }
+    Ok(out)
+}
@@ -0,0 +1,108 @@
+use std::collections::BTreeSet;
+
+use proc_macro2::TokenStream as TokenStream2;
+use quote::quote;
+use syn::{parse::{Parse, ParseStream}, punctuated::Punctuated, Expr, Ident, LitStr, Token};
+
+use surrealdb_util_params::{params, BUILTIN};
+
+pub struct SqlInput {
+    db: Expr,
+    sql: LitStr,
+    bindings: Punctuated<Binding, Token![,]>,
+}
+
+struct Binding {
+    name: Ident,
+    value: Option<Expr>,
+}
+
+impl Parse for SqlInput {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let db = input.parse()?;
+        input.parse::<Token![,]>()?;
+        let sql = input.parse()?;
+        let bindings = match input.is_empty() {
+            true => Punctuated::new(),
+            false => {
+                input.parse::<Token![,]>()?;
+                Punctuated::parse_terminated(input)?
+            },
+        };
+        Ok(Self { db, sql, bindings })
+    }
+}
+
+impl Parse for Binding {
+    fn parse(input: ParseStream) -> syn::Result<Self> {
+        let name = input.parse()?;
+        let value = match input.peek(Token![=]) {
+            true => {
+                input.parse::<Token![=]>()?;
+                Some(input.parse()?)
+            },
+            false => None,
+        };
+        Ok(Self { name, value })
+    }
+}
+
+pub fn expand(input: SqlInput) -> syn::Result<TokenStream2> {
+    let SqlInput { db, sql, bindings } = input;
+    let text = sql.value();
+    if let Err(e) = surrealdb::sql::parse(&text) {
+        return Err(syn::Error::new_spanned(&sql, e.to_string()));
+    }
+
+    let (used, defined) = params(&text);
+    let bound: BTreeSet<String> = bindings.iter().map(|b| b.name.to_string()).collect();
+    for name in &used {
+        if !bound.contains(name) && !defined.contains(name) && !BUILTIN.contains(&name.as_str()) {
+            return Err(syn::Error::new_spanned(&sql, format!("${name} has no binding")));
+        }
+    }
+    let mut args = Vec::with_capacity(bindings.len());
+    for binding in &bindings {
+        let name = &binding.name;
+        let key = name.to_string();
+        if !used.contains(&key) {
+            return Err(syn::Error::new_spanned(name, format!("${key} is not used in the query")));
+        }
+        let value = match &binding.value {
+            Some(value) => quote! { #value },
+            None => quote! { #name },
+        };
+        args.push(quote! { .arg(#key, #value) });
+    }
+
+    Ok(quote! {
+        (#db).query(#sql) #(#args)*
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn expand_str(input: &str) -> syn::Result<TokenStream2> {
+        expand(syn::parse_str(input)?)
+    }
+
+    #[test]
+    fn binds_placeholders() {
+        let tokens = expand_str(r#"db, "LET $n = 1; SELECT * FROM user WHERE age > $min AND name = $name AND $n = 1", min = 18, name"#).unwrap();
+        let expected = quote! {
+            (db).query("LET $n = 1; SELECT * FROM user WHERE age > $min AND name = $name AND $n = 1").arg("min", 18).arg("name", name)
+        };
+        assert_eq!(tokens.to_string(), expected.to_string());
+    }
+
+    #[test]
+    fn rejects_args_that_dont_match_placeholders() {
+        let err = expand_str(r#"db, "SELECT * FROM user WHERE age > $min AND name = $name", min = 18"#).unwrap_err();
+        assert_eq!(err.to_string(), "$name has no binding");
+        let err = expand_str(r#"db, "SELECT * FROM user WHERE name = '$min' AND age > $age", age, min = 18"#).unwrap_err();
+        assert_eq!(err.to_string(), "$min is not used in the query");
+        assert!(expand_str(r#"db, "SELECT * FROM $auth""#).is_ok());
+    }
+}
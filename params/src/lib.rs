@@ -0,0 +1,91 @@
+//! The `$param` scanner shared by `surrealdb_util`'s runtime parameter check and the
+//! compile-time check of its `sql!` macro.
+
+use std::collections::BTreeSet;
+
+/// Parameters the datastore provides itself.
+pub const BUILTIN: [&str; 11] = ["auth", "scope", "token", "session", "this", "parent", "value", "before", "after", "event", "input"];
+
+/// The names of the `$params` in `sql` outside strings, quoted identifiers and comments, and
+/// those of them assigned with LET.
+pub fn params(sql: &str) -> (BTreeSet<String>, BTreeSet<String>) {
+    let mut used = BTreeSet::new();
+    let mut defined = BTreeSet::new();
+    let chars: Vec<char> = sql.chars().collect();
+    let mut last_word = String::new();
+    let mut i = 0;
+    while i < chars.len() {
+        let c = chars[i];
+        match c {
+            '\'' | '"' | '`' | '⟨' => {
+                let close = if c == '⟨' { '⟩' } else { c };
+                i += 1;
+                while i < chars.len() && chars[i] != close {
+                    if chars[i] == '\\' {
+                        i += 1;
+                    }
+                    i += 1;
+                }
+                last_word.clear();
+            },
+            '-' | '/' if chars.get(i + 1) == Some(&c) => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+            },
+            '/' if chars.get(i + 1) == Some(&'*') => {
+                i += 2;
+                while i < chars.len() && !(chars[i] == '*' && chars.get(i + 1) == Some(&'/')) {
+                    i += 1;
+                }
+                i += 1;
+            },
+            '$' => {
+                let start = i + 1;
+                while chars.get(i + 1).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    i += 1;
+                }
+                let name: String = chars[start..=i].iter().collect();
+                if !name.is_empty() {
+                    if last_word.eq_ignore_ascii_case("let") {
+                        defined.insert(name.clone());
+                    }
+                    used.insert(name);
+                }
+                last_word.clear();
+            },
+            c if c.is_ascii_alphanumeric() || c == '_' => {
+                let start = i;
+                while chars.get(i + 1).is_some_and(|c| c.is_ascii_alphanumeric() || *c == '_') {
+                    i += 1;
+                }
+                last_word = chars[start..=i].iter().collect();
+            },
+            c if c.is_whitespace() => {},
+            _ => last_word.clear(),
+        }
+        i += 1;
+    }
+    (used, defined)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn names(names: &[&str]) -> BTreeSet<String> {
+        names.iter().map(|n| n.to_string()).collect()
+    }
+
+    #[test]
+    fn params_skip_strings_and_comments() {
+        let sql = "LET $x = 1; SELECT * FROM user WHERE a = $x AND b = '$no' AND c = ⟨$no⟩ -- $no\n\
+            AND d = \"it\\\"s $no\" /* $no */ AND e = $yes_2; # $no";
+        assert_eq!(params(sql), (names(&["x", "yes_2"]), names(&["x"])));
+    }
+}
@@ -0,0 +1,75 @@
+use std::sync::Arc;
+
+use surrealdb::{Session, sql::{Query, Statement, Statements, Value, statements::{DefineScopeStatement, OutputStatement}}};
+
+use crate::{ArgsBuilder, Db, Error, QueryBuilder, RecordId, Result};
+
+impl Db {
+    /// Runs the SIGNUP clause of `scope` in the current database with `params` as its
+    /// variables, and returns a handle whose queries run as the record it returned, with that
+    /// record as `$auth`. Fails with `Error::AuthFailed` when the scope doesn't exist, has no
+    /// SIGNUP clause or the clause returns no record.
+    pub async fn signup(&self, scope: &str, params: ArgsBuilder) -> Result<Db> {
+        self.scope_auth(scope, params, |sc| sc.signup).await
+    }
+
+    /// Like `signup`, running the SIGNIN clause of `scope`.
+    pub async fn signin(&self, scope: &str, params: ArgsBuilder) -> Result<Db> {
+        self.scope_auth(scope, params, |sc| sc.signin).await
+    }
+
+    async fn scope_auth(&self, scope: &str, params: ArgsBuilder, clause: fn(DefineScopeStatement) -> Option<Value>) -> Result<Db> {
+        let ns = self.sess.ns.clone().unwrap_or_default();
+        let db = self.sess.db.clone().unwrap_or_default();
+        // Read the stored definition itself rather than parsing it back from INFO FOR DB.
+        let mut tx = self.ds.transaction(false, false).await?;
+        let definition = tx.get_sc(&ns, &db, scope).await;
+        tx.cancel().await?;
+        let expr = match definition {
+            Ok(sc) => clause(sc).ok_or(Error::AuthFailed)?,
+            Err(surrealdb::Error::ScNotFound) => return Err(Error::AuthFailed),
+            Err(e) => return Err(e.into()),
+        };
+        let sql = format!("RETURN {expr}");
+        let query = Query(Statements(vec![Statement::Output(OutputStatement { what: expr })]));
+        let mut auth = QueryBuilder::prepared(self, sql.into(), Arc::new(query));
+        auth.args = params;
+        let record = auth.execute_value().await?.record().ok_or(Error::AuthFailed)?;
+
+        let mut user = self.clone();
+        user.sess = Session {
+            sd: Some(Value::Thing(record)),
+            ..Session::for_sc(ns, db, scope.to_string())
+        };
+        Ok(user)
+    }
+
+    /// The record the handle is authenticated as, for handles returned by `signup` and
+    /// `signin`.
+    pub fn auth_record(&self) -> Option<RecordId> {
+        match &self.sess.sd {
+            Some(Value::Thing(t)) => Some(t.clone().into()),
+            _ => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn signup_runs_scope_clause() {
+        let db = Db::memory("test", "auth").await.unwrap();
+        db.query("DEFINE SCOPE `my scope` SIGNUP (CREATE user SET name = $name) SIGNIN (SELECT * FROM user WHERE name = $name)")
+            .execute_check()
+            .await
+            .unwrap();
+        let mut params = ArgsBuilder::default();
+        params.arg("name", "a\"b'c");
+        let user = db.signup("my scope", params.clone()).await.unwrap();
+        let record = user.auth_record().unwrap();
+        assert_eq!(db.signin("my scope", params).await.unwrap().auth_record(), Some(record));
+        assert!(matches!(db.signin("missing", ArgsBuilder::default()).await, Err(Error::AuthFailed)));
+    }
+}
@@ -0,0 +1,108 @@
+use std::future::Future;
+
+use crate::{ArgsBuilder, Db, Error, IntoValue, Record, Result};
+
+/// The query surface of `Db`, so code can be written against either a real datastore or a
+/// `MockDb`. Most callers use `query`, which builds arguments like `QueryBuilder`.
+pub trait DbClient: Sync {
+    /// Returns one entry per statement, like `QueryBuilder::execute_all`.
+    fn execute_all(&self, sql: &str, args: ArgsBuilder, strict: bool) -> impl Future<Output = Result<Vec<Result<Vec<Record>>>>> + Send;
+
+    /// Runs `statements` in a single transaction sharing `args`, like `Transaction::commit`.
+    fn transaction(&self, statements: &[&str], args: ArgsBuilder, strict: bool) -> impl Future<Output = Result<Vec<Vec<Record>>>> + Send;
+
+    /// Whether queries built with `query` run in strict mode unless overridden.
+    fn default_strict(&self) -> bool {
+        false
+    }
+
+    fn query(&self, sql: &str) -> ClientQuery<'_, Self> where Self: Sized {
+        ClientQuery {
+            client: self,
+            sql: sql.to_string(),
+            args: Default::default(),
+            strict: self.default_strict(),
+        }
+    }
+}
+
+impl DbClient for Db {
+    fn execute_all(&self, sql: &str, args: ArgsBuilder, strict: bool) -> impl Future<Output = Result<Vec<Result<Vec<Record>>>>> + Send {
+        let mut query = Db::query(self, sql.to_string());
+        query.args = args;
+        query.strict(strict).execute_all()
+    }
+
+    fn transaction(&self, statements: &[&str], args: ArgsBuilder, strict: bool) -> impl Future<Output = Result<Vec<Vec<Record>>>> + Send {
+        let tx = self.begin().strict(strict);
+        if let Some((last, rest)) = statements.split_last() {
+            for stmt in rest {
+                tx.query(stmt);
+            }
+            let mut query = tx.query(last);
+            for (k, v) in args.0 {
+                query = query.arg(k, v);
+            }
+        }
+        tx.commit()
+    }
+
+    fn default_strict(&self) -> bool {
+        self.strict
+    }
+}
+
+/// A query against any `DbClient`, returned by `DbClient::query`.
+pub struct ClientQuery<'a, C> {
+    client: &'a C,
+    sql: String,
+    args: ArgsBuilder,
+    strict: bool,
+}
+
+impl <'a, C: DbClient> ClientQuery<'a, C> {
+    pub fn arg<S: Into<String>, V: IntoValue>(mut self, key: S, value: V) -> Self {
+        self.args.arg(key, value);
+        self
+    }
+
+    pub fn arg_opt<S: Into<String>, V: IntoValue>(mut self, key: S, value: Option<V>) -> Self {
+        self.args.arg_opt(key, value);
+        self
+    }
+
+    pub fn args(mut self, args: ArgsBuilder) -> Self {
+        self.args = args;
+        self
+    }
+
+    /// Runs this query in strict mode or not, instead of following the client.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub async fn execute_all(self) -> Result<Vec<Result<Vec<Record>>>> {
+        self.client.execute_all(&self.sql, self.args, self.strict).await
+    }
+
+    pub async fn execute(self) -> Result<Vec<Record>> {
+        self.execute_all().await?.into_iter().next().unwrap_or(Ok(Default::default()))
+    }
+
+    pub async fn execute_one(self) -> Result<Record> {
+        let mut records = self.execute().await?;
+        match records.len() {
+            0 => Err(Error::NotFound),
+            1 => Ok(records.remove(0)),
+            n => Err(Error::TooManyRows(n)),
+        }
+    }
+
+    pub async fn execute_check(self) -> Result<()> {
+        for res in self.execute_all().await? {
+            res?;
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,425 @@
+use std::{collections::BTreeMap, io::{BufRead, BufReader, Read, Write}, pin::pin, str::FromStr};
+
+use futures::TryStreamExt;
+use serde::{Deserialize, Serialize};
+use bigdecimal::BigDecimal;
+use chrono::{DateTime, Utc};
+use surrealdb::sql::{Duration, Geometry, Id, Number, Object, Thing, Value};
+use uuid::Uuid;
+
+use crate::{from_json, ident, Db, Error, FromValue, Record, RecordId, Result};
+
+const BATCH: usize = 1000;
+
+/// The line of an `export` dump after which every line is a record.
+const DATA: &str = "-- DATA";
+
+/// A record in the data section of an `export` dump.
+#[derive(Serialize, Deserialize)]
+struct DumpRecord {
+    table: String,
+    id: serde_json::Value,
+    content: serde_json::Value,
+}
+
+fn id_to_json(id: &Id) -> Result<serde_json::Value> {
+    match id {
+        Id::Number(n) => Ok((*n).into()),
+        Id::String(s) => Ok(s.as_str().into()),
+        Id::Array(arr) => encode(&Value::Array(arr.clone())),
+        Id::Object(obj) => encode(&Value::Object(obj.clone())),
+    }
+}
+
+fn id_from_json(id: serde_json::Value) -> Result<Id> {
+    match decode(id)? {
+        Value::Number(Number::Int(n)) => Ok(Id::Number(n)),
+        Value::Strand(s) => Ok(Id::String(s.0)),
+        Value::Array(arr) => Ok(Id::Array(arr)),
+        Value::Object(obj) => Ok(Id::Object(obj)),
+        v => Err(Error::cast_failed::<RecordId>(&v)),
+    }
+}
+
+/// Encodes `value` as JSON that `decode` turns back into the same value. Values JSON has no
+/// type for are written as an object with a single `$`-prefixed key naming their kind, such
+/// as `{"$datetime": "2024-05-01T09:30:00Z"}`; objects that happen to have that shape are
+/// wrapped in `{"$object": ...}`.
+fn encode(value: &Value) -> Result<serde_json::Value> {
+    let tagged = |tag: &str, v: serde_json::Value| serde_json::Value::Object([(tag.to_string(), v)].into_iter().collect());
+    Ok(match value {
+        Value::None => tagged("$none", true.into()),
+        Value::Null => serde_json::Value::Null,
+        Value::True => true.into(),
+        Value::False => false.into(),
+        Value::Number(Number::Int(n)) => (*n).into(),
+        Value::Number(Number::Float(n)) => (*n).into(),
+        Value::Number(Number::Decimal(n)) => tagged("$decimal", n.to_string().into()),
+        Value::Strand(s) => s.as_str().into(),
+        Value::Datetime(dt) => tagged("$datetime", dt.0.to_rfc3339().into()),
+        Value::Duration(d) => tagged("$duration", d.to_string().into()),
+        Value::Uuid(u) => tagged("$uuid", u.0.to_string().into()),
+        Value::Thing(t) => tagged("$thing", serde_json::json!({ "tb": t.tb, "id": id_to_json(&t.id)? })),
+        Value::Geometry(g) => tagged("$geometry", serde_json::to_value(g)?),
+        Value::Array(arr) => arr.iter().map(encode).collect::<Result<Vec<_>>>()?.into(),
+        Value::Object(obj) => {
+            let fields = obj.iter().map(|(k, v)| Ok((k.clone(), encode(v)?))).collect::<Result<serde_json::Map<_, _>>>()?;
+            match fields.len() == 1 && fields.keys().all(|k| k.starts_with('$')) {
+                true => tagged("$object", fields.into()),
+                false => fields.into(),
+            }
+        },
+        v => return Err(Error::cast_failed::<serde_json::Value>(v)),
+    })
+}
+
+fn decode(value: serde_json::Value) -> Result<Value> {
+    let serde_json::Value::Object(mut obj) = value else {
+        return match value {
+            serde_json::Value::Array(arr) => Ok(Value::Array(arr.into_iter().map(decode).collect::<Result<Vec<_>>>()?.into())),
+            v => Ok(from_json(v)),
+        };
+    };
+    let tag = match (obj.len(), obj.keys().next()) {
+        (1, Some(k)) if k.starts_with('$') => k.clone(),
+        _ => return decode_object(obj),
+    };
+    let inner = obj.remove(&tag).unwrap_or_default();
+    let text = inner.as_str().unwrap_or_default();
+    let value = match tag.as_str() {
+        "$none" => Value::None,
+        "$decimal" => BigDecimal::from_str(text).map(Value::from).map_err(|_e| Error::cast_failed::<BigDecimal>(&from_json(inner)))?,
+        "$datetime" => DateTime::parse_from_rfc3339(text)
+            .map(|dt| Value::from(dt.with_timezone(&Utc)))
+            .map_err(|_e| Error::cast_failed::<DateTime<Utc>>(&from_json(inner)))?,
+        "$duration" => Duration::from(text).into(),
+        "$uuid" => Uuid::from_str(text).map(|u| surrealdb::sql::Uuid(u).into()).map_err(|_e| Error::cast_failed::<Uuid>(&from_json(inner)))?,
+        "$thing" => {
+            let thing: DumpThing = serde_json::from_value(inner)?;
+            Value::Thing(Thing { tb: thing.tb, id: id_from_json(thing.id)? })
+        },
+        "$geometry" => match surrealdb::sql::json(&inner.to_string())? {
+            Value::Geometry(g) => Value::Geometry(g),
+            v => return Err(Error::cast_failed::<Geometry>(&v)),
+        },
+        "$object" => match inner {
+            serde_json::Value::Object(obj) => return decode_object(obj),
+            v => return Err(Error::cast_failed::<Object>(&from_json(v))),
+        },
+        _ => {
+            obj.insert(tag, inner);
+            return decode_object(obj);
+        },
+    };
+    Ok(value)
+}
+
+fn decode_object(obj: serde_json::Map<String, serde_json::Value>) -> Result<Value> {
+    let fields = obj.into_iter().map(|(k, v)| Ok((k, decode(v)?))).collect::<Result<BTreeMap<_, _>>>()?;
+    Ok(Value::Object(Object(fields)))
+}
+
+/// `thing` as a record id literal. Fails with `Error::InvalidIdent` for ids that can't be
+/// written safely: strings holding both `⟩` and a backslash, and arrays or objects holding
+/// anything other than strings, numbers, booleans and NULL.
+fn thing_literal(thing: &Thing) -> Result<String> {
+    let invalid = || Error::InvalidIdent(thing.to_string());
+    let id = match &thing.id {
+        Id::Number(n) => n.to_string(),
+        Id::String(s) if !s.contains('⟩') => format!("⟨{s}⟩"),
+        Id::String(s) if !s.contains('\\') => format!("`{}`", s.replace('`', "\\`")),
+        Id::String(_) => return Err(invalid()),
+        Id::Array(arr) => id_literal(&Value::Array(arr.clone())).ok_or_else(invalid)?,
+        Id::Object(obj) => id_literal(&Value::Object(obj.clone())).ok_or_else(invalid)?,
+    };
+    Ok(format!("{}:{id}", ident(&thing.tb)?))
+}
+
+fn id_literal(value: &Value) -> Option<String> {
+    Some(match value {
+        Value::Null|Value::True|Value::False|Value::Number(_) => value.to_string(),
+        Value::Strand(s) => serde_json::to_string(s.as_str()).ok()?,
+        Value::Array(arr) => format!("[{}]", arr.iter().map(id_literal).collect::<Option<Vec<_>>>()?.join(", ")),
+        Value::Object(obj) => {
+            let fields = obj.iter().map(|(k, v)| Some(format!("{}: {}", serde_json::to_string(k).ok()?, id_literal(v)?)));
+            format!("{{ {} }}", fields.collect::<Option<Vec<_>>>()?.join(", "))
+        },
+        _ => return None,
+    })
+}
+
+#[derive(Deserialize)]
+struct DumpThing {
+    tb: String,
+    id: serde_json::Value,
+}
+
+/// The tables in the current database with their DEFINE TABLE statements, keyed by the
+/// escaped identifiers INFO reports them by and limited to `only` when given.
+async fn tables(db: &Db, only: Option<&[&str]>) -> Result<Vec<(String, String)>> {
+    let info = db.query("INFO FOR DB").execute_one().await?;
+    let tables = definitions(&info, "tb")?;
+    match only {
+        None => Ok(tables),
+        Some(only) => {
+            let only = only.iter().map(|t| ident(t)).collect::<Result<Vec<_>>>()?;
+            Ok(tables.into_iter().filter(|(k, _)| only.contains(k)).collect())
+        },
+    }
+}
+
+/// The names and DEFINE statements INFO reports under `key`, in name order.
+fn definitions(info: &Record, key: &str) -> Result<Vec<(String, String)>> {
+    Ok(info.get_as::<Option<Vec<(String, String)>>>(key)?.unwrap_or_default())
+}
+
+impl Db {
+    /// Writes a dump of every table in the current database: the table, field, index and
+    /// event definitions as SurrealQL, then a `-- DATA` line followed by every record as a
+    /// JSON object per line. `import` loads it back. Values JSON has no type for, such as
+    /// record links and datetimes, are written as tagged objects so they come back with the
+    /// same type.
+    pub async fn export(&self, writer: impl Write) -> Result<()> {
+        self.export_with(writer, None).await
+    }
+
+    /// Like `export`, limited to the named tables. Names that don't exist are skipped.
+    pub async fn export_tables(&self, writer: impl Write, tables: &[&str]) -> Result<()> {
+        self.export_with(writer, Some(tables)).await
+    }
+
+    async fn export_with(&self, mut writer: impl Write, only: Option<&[&str]>) -> Result<()> {
+        let tables = tables(self, only).await?;
+        writeln!(writer, "OPTION IMPORT;\n")?;
+        for (table, def) in &tables {
+            let info = self.query(format!("INFO FOR TABLE {table}")).execute_one().await?;
+            writeln!(writer, "-- TABLE: {table}\n")?;
+            writeln!(writer, "{def};")?;
+            for key in ["fd", "ix", "ev"] {
+                for (_, def) in definitions(&info, key)? {
+                    writeln!(writer, "{def};")?;
+                }
+            }
+            writeln!(writer)?;
+        }
+        writeln!(writer, "{DATA}")?;
+        for (table, _) in &tables {
+            let mut records = pin!(self.query(format!("SELECT * FROM {table}")).execute_stream(BATCH).await?);
+            while let Some(mut record) = records.try_next().await? {
+                let Thing { tb, id } = record.take::<RecordId>("id")?.into();
+                let line = DumpRecord {
+                    table: tb,
+                    id: id_to_json(&id)?,
+                    content: encode(&Value::Object(record.0))?,
+                };
+                serde_json::to_writer(&mut writer, &line)?;
+                writeln!(writer)?;
+            }
+        }
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Loads a dump written by `export`: runs the SurrealQL before its `-- DATA` line, then
+    /// writes the records after it, with their ids and contents bound as parameters. Records
+    /// with `in` and `out` links are restored with RELATE so graph traversals work again.
+    /// The dump is read line by line and records are written in transactions of up to 1000,
+    /// so a failure leaves the batches before it in place. Input without a `-- DATA` line is
+    /// run as SurrealQL.
+    pub async fn import(&self, reader: impl Read) -> Result<()> {
+        let mut lines = BufReader::new(reader).lines();
+        let mut sql = String::new();
+        for line in lines.by_ref() {
+            let line = line?;
+            if line == DATA {
+                break;
+            }
+            sql.push_str(&line);
+            sql.push('\n');
+        }
+        if !sql.trim().is_empty() {
+            self.query(sql).execute_check().await?;
+        }
+        let mut batch = Vec::new();
+        for (n, line) in lines.enumerate() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let record: DumpRecord = serde_json::from_str(&line)?;
+            let id = Thing {
+                tb: record.table,
+                id: id_from_json(record.id).map_err(|e| e.at_key(format!("{n}.id")))?,
+            };
+            let data = match decode(record.content).map_err(|e| e.at_key(n))? {
+                Value::Object(data) => data,
+                v => return Err(Error::cast_failed::<Record>(&v).at_key(n)),
+            };
+            batch.push((id, data));
+            if batch.len() == BATCH {
+                self.import_batch(std::mem::take(&mut batch)).await?;
+            }
+        }
+        if !batch.is_empty() {
+            self.import_batch(batch).await?;
+        }
+        Ok(())
+    }
+
+    async fn import_batch(&self, records: Vec<(Thing, Object)>) -> Result<()> {
+        let tx = self.begin();
+        tx.query("OPTION IMPORT");
+        for (n, (id, data)) in records.into_iter().enumerate() {
+            if let (Some(Value::Thing(from)), Some(Value::Thing(to))) = (data.get("in"), data.get("out")) {
+                // RELATE only takes a record id written into the statement, so the edge is
+                // created under its id first and its content written separately.
+                tx.query(&format!("RELATE ($in_{n})->{}->($out_{n}) SET id = {}", ident(&id.tb)?, thing_literal(&id)?))
+                    .arg(format!("in_{n}"), from.clone())
+                    .arg(format!("out_{n}"), to.clone());
+            }
+            tx.query(&format!("UPDATE $id_{n} CONTENT $data_{n}"))
+                .arg(format!("id_{n}"), id)
+                .arg(format!("data_{n}"), data);
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+
+    /// Writes every record of `table` as one JSON object per line and returns how many were
+    /// written. Record ids are written as `table:id` strings.
+    pub async fn export_json(&self, mut writer: impl Write, table: &str) -> Result<usize> {
+        let mut records = pin!(self.query(format!("SELECT * FROM {}", ident(table)?)).execute_stream(BATCH).await?);
+        let mut n = 0;
+        while let Some(record) = records.try_next().await? {
+            serde_json::to_writer(&mut writer, &record)?;
+            writeln!(writer)?;
+            n += 1;
+        }
+        writer.flush()?;
+        Ok(n)
+    }
+
+    /// Loads records written by `export_json` into `table`, in transactions of up to 1000,
+    /// and returns how many were loaded. A record with an `id` keeps it, replacing any
+    /// existing record; blank lines are skipped.
+    pub async fn import_json(&self, reader: impl Read, table: &str) -> Result<usize> {
+        ident(table)?;
+        let mut tx = self.begin();
+        let mut n = 0;
+        for line in BufReader::new(reader).lines() {
+            let line = line?;
+            if line.trim().is_empty() {
+                continue;
+            }
+            let mut data = match from_json(serde_json::from_str(&line)?) {
+                Value::Object(data) => data,
+                v => return Err(Error::cast_failed::<Record>(&v).at_key(n)),
+            };
+            match data.remove("id") {
+                Some(id) => {
+                    let id = RecordId::from_value(id).map_err(|e| e.at_key(format!("{n}.id")))?;
+                    tx.query(&format!("UPDATE $id_{n} CONTENT $data_{n}"))
+                        .arg(format!("id_{n}"), RecordId::new(table, id.id))
+                        .arg(format!("data_{n}"), data);
+                },
+                None => {
+                    tx.query(&format!("CREATE type::table($table_{n}) CONTENT $data_{n}"))
+                        .arg(format!("table_{n}"), table)
+                        .arg(format!("data_{n}"), data);
+                },
+            }
+            n += 1;
+            if n % BATCH == 0 {
+                std::mem::replace(&mut tx, self.begin()).commit().await?;
+            }
+        }
+        tx.commit().await?;
+        Ok(n)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use surrealdb::sql::Value;
+
+    use crate::Db;
+
+    #[tokio::test]
+    async fn export_round_trips_quotes() {
+        let db = Db::memory("test", "export").await.unwrap();
+        db.query("DEFINE TABLE user SCHEMALESS; DEFINE FIELD name ON user TYPE string")
+            .execute_check()
+            .await
+            .unwrap();
+        let payload = r#"z" }; CREATE pwned:1; UPDATE tmp:1 CONTENT { a: "#;
+        db.query("CREATE user:1 SET name = $name, nested = { quote: $quote, list: [1, 'a\\b'] }")
+            .arg("name", payload)
+            .arg("quote", "say \"hi\" 'there'")
+            .execute_check()
+            .await
+            .unwrap();
+        db.query("CREATE type::thing('user', $id) SET name = 'odd id'")
+            .arg("id", "a⟩\"b")
+            .execute_check()
+            .await
+            .unwrap();
+        db.query("CREATE user:[1, 'x'] SET name = 'array id'").execute_check().await.unwrap();
+
+        let mut dump = Vec::new();
+        db.export(&mut dump).await.unwrap();
+        let copy = Db::memory("test", "import").await.unwrap();
+        copy.import(dump.as_slice()).await.unwrap();
+
+        let all = "SELECT * FROM user ORDER BY id";
+        let records = db.query(all).execute().await.unwrap();
+        assert_eq!(records.len(), 3);
+        assert_eq!(copy.query(all).execute().await.unwrap(), records);
+        assert!(copy.query("SELECT * FROM pwned, tmp").execute().await.unwrap().is_empty());
+        let info = copy.query("INFO FOR TABLE user").execute_one().await.unwrap();
+        assert!(info.get("fd.name").is_ok());
+    }
+
+    #[tokio::test]
+    async fn export_keeps_value_types() {
+        let db = Db::memory("test", "export_types").await.unwrap();
+        db.query("CREATE post:1 SET author = user:1, at = <datetime> '2024-05-01T09:30:00Z', ttl = 1h30m, \
+            uid = $uid, price = <decimal> '1.10', \
+            link = $link, day = $day, tag = { '$datetime': 'not a date' }")
+            .arg("uid", uuid::Uuid::from_u128(7))
+            .arg("link", "re:hello")
+            .arg("day", "2024-05-01")
+            .execute_check()
+            .await
+            .unwrap();
+        let mut dump = Vec::new();
+        db.export(&mut dump).await.unwrap();
+        let copy = Db::memory("test", "import_types").await.unwrap();
+        copy.import(dump.as_slice()).await.unwrap();
+        let all = "SELECT * FROM post";
+        let records = copy.query(all).execute().await.unwrap();
+        assert_eq!(records, db.query(all).execute().await.unwrap());
+        assert!(matches!(records[0].get("at"), Ok(Value::Datetime(_))));
+        assert!(matches!(records[0].get("author"), Ok(Value::Thing(_))));
+        assert_eq!(records[0].get_as::<String>("day").unwrap(), "2024-05-01");
+    }
+
+    #[tokio::test]
+    async fn export_restores_edges() {
+        let db = Db::memory("test", "export_edges").await.unwrap();
+        db.query("CREATE person:1; CREATE post:1; CREATE post:2; \
+            RELATE person:1->wrote->post:1 SET at = 1; RELATE person:1->wrote->post:2 SET at = 2; \
+            RELATE post:2->cites->post:1 SET id = cites:⟨a`b\\c⟩; RELATE post:1->cites->post:2 SET id = cites:[1, 'x']")
+            .execute_check()
+            .await
+            .unwrap();
+        let mut dump = Vec::new();
+        db.export(&mut dump).await.unwrap();
+        let copy = Db::memory("test", "import_edges").await.unwrap();
+        copy.import(dump.as_slice()).await.unwrap();
+        for sql in ["SELECT * FROM wrote ORDER BY id", "SELECT ->wrote->post AS posts FROM person", "SELECT <-wrote<-person AS people, ->cites->post AS cites FROM post ORDER BY id", "SELECT * FROM cites ORDER BY id"] {
+            let records = copy.query(sql).execute().await.unwrap();
+            assert!(!records.is_empty());
+            assert_eq!(records, db.query(sql).execute().await.unwrap(), "{sql}");
+        }
+    }
+}
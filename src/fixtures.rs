@@ -0,0 +1,114 @@
+use std::{fs, path::Path};
+
+use surrealdb::sql::Value;
+
+use crate::{ident, Db, Error, IntoValue, Record, Result};
+
+#[derive(Debug, Clone)]
+enum Fixture {
+    Record { table: String, data: Value },
+    Sql(String),
+}
+
+/// Records and SQL scripts to load into a database with `Db::seed`, in the order added.
+#[derive(Debug, Clone, Default)]
+pub struct FixtureSet(Vec<Fixture>);
+
+impl FixtureSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a record to create in `table`. Include an `id` field to fix its record id.
+    pub fn record<D: IntoValue>(mut self, table: &str, data: D) -> Self {
+        self.0.push(Fixture::Record {
+            table: table.to_string(),
+            data: data.into_value(),
+        });
+        self
+    }
+
+    pub fn sql(mut self, sql: &str) -> Self {
+        self.0.push(Fixture::Sql(sql.to_string()));
+        self
+    }
+
+    /// Adds the records in a JSON object that maps table names to arrays of records, e.g.
+    /// `{"user": [{"id": "alice", "age": 30}]}`.
+    pub fn json(mut self, json: &str) -> Result<Self> {
+        let tables = match surrealdb::sql::json(json)? {
+            Value::Object(tables) => tables,
+            v => return Err(Error::cast_failed::<FixtureSet>(&v)),
+        };
+        for (table, records) in tables {
+            let Value::Array(records) = records else {
+                return Err(Error::cast_failed::<Vec<Record>>(&records).at_key(table));
+            };
+            for data in records {
+                self = self.record(&table, data);
+            }
+        }
+        Ok(self)
+    }
+
+    /// Adds the contents of a `.json` file (see `json`) or any other file as SurrealQL.
+    pub fn file(self, path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let contents = fs::read_to_string(path)?;
+        match path.extension().is_some_and(|ext| ext == "json") {
+            true => self.json(&contents),
+            false => Ok(self.sql(&contents)),
+        }
+    }
+}
+
+impl Db {
+    /// Loads every fixture in a single transaction and returns the records produced, in
+    /// order.
+    pub async fn seed(&self, fixtures: &FixtureSet) -> Result<Vec<Record>> {
+        let tx = self.begin();
+        for (i, fixture) in fixtures.0.iter().enumerate() {
+            match fixture {
+                // CONTENT doesn't set the id of a record created from a table name, so a
+                // given id is passed to type::thing instead.
+                Fixture::Record { table, data } => {
+                    ident(table)?;
+                    match data.pick(&["id".into()]) {
+                        Value::None => {
+                            tx.query(&format!("CREATE type::table($table_{i}) CONTENT $data_{i}"))
+                                .arg(format!("table_{i}"), table.as_str())
+                                .arg(format!("data_{i}"), data.clone());
+                        },
+                        id => {
+                            tx.query(&format!("CREATE type::thing($table_{i}, $id_{i}) CONTENT $data_{i}"))
+                                .arg(format!("table_{i}"), table.as_str())
+                                .arg(format!("id_{i}"), id)
+                                .arg(format!("data_{i}"), data.clone());
+                        },
+                    }
+                },
+                Fixture::Sql(sql) => {
+                    tx.query(sql);
+                },
+            }
+        }
+        Ok(tx.commit().await?.into_iter().flatten().collect())
+    }
+
+    /// Deletes every record of every table in the current database, keeping table, field
+    /// and index definitions. The `_migrations` table is left alone, since the schema it
+    /// describes is kept.
+    pub async fn truncate_all(&self) -> Result<()> {
+        let info = self.query("INFO FOR DB").execute_one().await?;
+        let tables: Vec<String> = info.get_as::<Option<Record>>("tb")?
+            .map(|tb| tb.0.keys().filter(|t| *t != "_migrations").cloned().collect())
+            .unwrap_or_default();
+        // INFO keys are table names already escaped as identifiers, so they are used as is.
+        let tx = self.begin();
+        for table in tables {
+            tx.query(&format!("DELETE {table}"));
+        }
+        tx.commit().await?;
+        Ok(())
+    }
+}
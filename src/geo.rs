@@ -0,0 +1,88 @@
+use geo_types::{LineString, MultiLineString, MultiPoint, MultiPolygon, Point, Polygon};
+use surrealdb::sql::{Geometry, Value};
+
+use crate::{Error, FromValue, IntoValue, Result};
+
+macro_rules! geometry {
+    ($($ty:ident => $variant:ident),* $(,)?) => {
+        $(
+            impl FromValue for $ty<f64> {
+                fn from_value(value: Value) -> Result<Self> {
+                    match value {
+                        Value::Geometry(Geometry::$variant(v)) => Ok(v),
+                        _ => Err(Error::cast_failed::<Self>(&value)),
+                    }
+                }
+            }
+
+            impl IntoValue for $ty<f64> {
+                fn into_value(self) -> Value {
+                    Value::Geometry(self.into())
+                }
+            }
+        )*
+    };
+}
+
+geometry! {
+    Point => Point,
+    LineString => Line,
+    Polygon => Polygon,
+    MultiPoint => MultiPoint,
+    MultiLineString => MultiLine,
+    MultiPolygon => MultiPolygon,
+}
+
+impl FromValue for geo_types::Geometry<f64> {
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Geometry(g) => Ok(g.into()),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
+/// Lines, rectangles and triangles, which SurrealQL has no geometry for, are sent as a line
+/// string and polygons respectively.
+impl IntoValue for geo_types::Geometry<f64> {
+    fn into_value(self) -> Value {
+        Value::Geometry(geometry(self))
+    }
+}
+
+fn geometry(g: geo_types::Geometry<f64>) -> Geometry {
+    match g {
+        geo_types::Geometry::Point(v) => v.into(),
+        geo_types::Geometry::Line(v) => LineString::from(vec![v.start, v.end]).into(),
+        geo_types::Geometry::LineString(v) => v.into(),
+        geo_types::Geometry::Polygon(v) => v.into(),
+        geo_types::Geometry::MultiPoint(v) => v.into(),
+        geo_types::Geometry::MultiLineString(v) => v.into(),
+        geo_types::Geometry::MultiPolygon(v) => v.into(),
+        geo_types::Geometry::GeometryCollection(v) => Geometry::Collection(v.0.into_iter().map(geometry).collect()),
+        geo_types::Geometry::Rect(v) => v.to_polygon().into(),
+        geo_types::Geometry::Triangle(v) => v.to_polygon().into(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use geo_types::{coord, GeometryCollection, Line, Rect};
+
+    use super::*;
+
+    #[test]
+    fn geometry_round_trips() {
+        let point = geo_types::Geometry::Point(Point::new(1.0, 2.0));
+        assert_eq!(geo_types::Geometry::from_value(point.clone().into_value()).unwrap(), point);
+        let line = Line::new(coord! { x: 0.0, y: 0.0 }, coord! { x: 1.0, y: 1.0 });
+        let collection = GeometryCollection(vec![point.clone(), geo_types::Geometry::Line(line)]);
+        let expected = GeometryCollection(vec![point, geo_types::Geometry::LineString(LineString::from(vec![line.start, line.end]))]);
+        assert_eq!(
+            geo_types::Geometry::from_value(geo_types::Geometry::GeometryCollection(collection).into_value()).unwrap(),
+            geo_types::Geometry::GeometryCollection(expected),
+        );
+        let rect = Rect::new(coord! { x: 0.0, y: 0.0 }, coord! { x: 1.0, y: 1.0 });
+        assert_eq!(geo_types::Geometry::Rect(rect).into_value(), rect.to_polygon().into_value());
+    }
+}
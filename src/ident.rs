@@ -0,0 +1,56 @@
+use crate::{Error, Result};
+
+/// Checks that `name` can be used as a table or field name in SurrealQL and returns it.
+/// Only names made of ASCII letters, digits and underscores are accepted; anything else
+/// fails with `Error::InvalidIdent`. Quoting is not an option: the datastore stores a table
+/// created under a backtick-quoted name, or through `type::table`, with the quotes as part
+/// of its name, so its records could not be read back under the name they were written to.
+pub fn ident(name: &str) -> Result<String> {
+    match !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        true => Ok(name.to_string()),
+        false => Err(Error::InvalidIdent(name.to_string())),
+    }
+}
+
+/// Checks a dotted field path such as `address.city` the way `ident` checks a name, part by
+/// part; a part may also be `*`, as in `tags.*`.
+pub(crate) fn ident_path(path: &str) -> Result<String> {
+    match path.split('.').all(|part| part == "*" || ident(part).is_ok()) {
+        true => Ok(path.to_string()),
+        false => Err(Error::InvalidIdent(path.to_string())),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_names_that_need_quoting() {
+        assert_eq!(ident("user_2").unwrap(), "user_2");
+        for name in ["", "my table", "a`b", "a\\b", "a\0b", "user-account", "é"] {
+            assert!(matches!(ident(name), Err(Error::InvalidIdent(n)) if n == name));
+        }
+    }
+
+    #[test]
+    fn checks_paths_part_by_part() {
+        for path in ["name", "address.city", "tags.*", "*"] {
+            assert_eq!(ident_path(path).unwrap(), path);
+        }
+        for path in ["", "address.", ".city", "a b.c", "name, password"] {
+            assert!(matches!(ident_path(path), Err(Error::InvalidIdent(p)) if p == path));
+        }
+    }
+
+    #[tokio::test]
+    async fn tables_are_read_back_by_name() {
+        let db = crate::Db::memory("test", "ident").await.unwrap();
+        db.create("my_t2", crate::Record::default()).await.unwrap();
+        assert_eq!(db.select_from("my_t2").execute().await.unwrap().len(), 1);
+        assert_eq!(db.count("my_t2", None).await.unwrap(), 1);
+        assert!(matches!(db.create("my t2", crate::Record::default()).await, Err(Error::InvalidIdent(_))));
+        assert!(matches!(db.select_from("my t2").execute().await, Err(Error::InvalidIdent(_))));
+        assert!(matches!(db.count("my t2", None).await, Err(Error::InvalidIdent(_))));
+    }
+}
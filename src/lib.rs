@@ -1,87 +1,1140 @@
-use std::{collections::BTreeMap, time::Duration, str::FromStr};
+use std::{borrow::Cow, collections::{BTreeMap, HashMap}, fmt, future::Future, hash::{BuildHasher, Hash}, ops::Deref, sync::{Arc, Mutex, PoisonError}, time::{Duration, Instant}, str::FromStr};
 use bigdecimal::BigDecimal;
-use chrono::{DateTime, Utc};
-use surrealdb::{Datastore, Session, sql::{Value, Object}};
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveDateTime, TimeZone, Utc};
+use futures::{stream, Stream, StreamExt, TryStreamExt};
+use surrealdb::{Datastore, Response, Session, sql::{Value, Array, Object, Function, Query, Limit, Start, Statement, Statements, Subquery, Thing, Timeout, statements::OutputStatement}};
+use tokio::sync::{Semaphore, SemaphorePermit};
+use prepared::StatementCache;
+use trace::QueryTrace;
 use thiserror::Error;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize, de::DeserializeOwned};
 use uuid::Uuid;
 
+mod auth;
+mod client;
+mod export;
+mod fixtures;
+#[cfg(feature = "geo")]
+mod geo;
+mod ident;
+mod middleware;
+mod migrations;
+mod mock;
+mod observer;
+mod page;
+mod params;
+mod prepared;
+mod pretty;
+mod read_only;
+mod redact;
+mod record_id;
+mod relate;
+#[cfg(feature = "remote")]
+mod remote;
+mod retry;
+pub mod schema;
+mod select;
+mod table;
+#[cfg(feature = "test-util")]
+mod testing;
+mod trace;
+mod transaction;
+mod validate;
+
+pub use client::{ClientQuery, DbClient};
+pub use fixtures::FixtureSet;
+pub use ident::ident;
+pub use middleware::QueryMiddleware;
+pub use migrations::Migration;
+pub use mock::MockDb;
+pub use observer::{QueryObserver, QueryStats};
+pub use page::Page;
+pub use prepared::PreparedQuery;
+pub use record_id::RecordId;
+pub use redact::redact_sql;
+pub use relate::RelateBuilder;
+#[cfg(feature = "remote")]
+pub use remote::RemoteDb;
+pub use select::{Order, SelectBuilder};
+pub use retry::RetryPolicy;
+pub use surrealdb;
+pub use surrealdb_util_derive::{sql, FromRecord, ValueEnum};
+pub use table::Table;
+pub use transaction::{Transaction, TransactionQuery};
+pub use validate::{FieldError, RecordSchema};
+
 #[derive(Debug, Error)]
 pub enum Error {
-    #[error("cast failed")]
-    CastFailed,
+    /// `key` is the dotted path to the value within the record or collection being cast,
+    /// when there is one.
+    #[error("cast failed{}: expected {expected}, found {found}", key.as_ref().map(|k| format!(" for key '{k}'")).unwrap_or_default())]
+    CastFailed {
+        key: Option<String>,
+        expected: &'static str,
+        found: ValueKind,
+    },
+    #[error("record not found")]
+    NotFound,
+    #[error("expected at most one record, got {0}")]
+    TooManyRows(usize),
     #[error("invalid record key '{0}'")]
-    InvalidKey(&'static str),
+    InvalidKey(String),
+    #[error("invalid identifier '{0}'")]
+    InvalidIdent(String),
+    /// The query uses a `$param` that isn't bound. Only checked with `Db::check_params`.
+    #[error("query parameter ${0} is not bound")]
+    MissingParam(String),
+    #[error("ambiguous record key '{0}' (matches {1:?})")]
+    AmbiguousKey(String, Vec<String>),
+    #[error("authentication failed")]
+    AuthFailed,
+    #[error("query writes through a read-only handle")]
+    ReadOnly,
+    /// Returned by `Db::update_if_version` when the record isn't at the expected version.
+    #[error("record is not at version {0}")]
+    VersionConflict(u64),
+    #[error("query is not a single SELECT statement")]
+    NotSelect,
+    /// Returned by `QueryBuilder::paginate` for page 0; pages are numbered from 1.
+    #[error("page numbers start at 1")]
+    InvalidPage,
+    #[error("query timed out")]
+    Timeout,
+    /// The transaction clashed with a concurrent write. Retrying it may succeed, which is
+    /// what `Db::retry_policy` does.
+    #[error("transaction conflict: {0}")]
+    Conflict(surrealdb::Error),
+    /// A statement failed. `index` is its position in `sql`, counting from 0.
+    #[error("statement {index} of `{sql}` failed: {source}")]
+    Query {
+        sql: String,
+        index: usize,
+        source: Box<Error>,
+    },
+    /// A `RemoteDb` server rejected the request or failed a statement.
+    #[cfg(feature = "remote")]
+    #[error("server error: {0}")]
+    Remote(String),
+    #[cfg(feature = "remote")]
+    #[error(transparent)]
+    Http(#[from] reqwest::Error),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
     #[error(transparent)]
-    Surrealdb(#[from] surrealdb::Error),
+    Json(#[from] serde_json::Error),
+    #[error(transparent)]
+    Surrealdb(surrealdb::Error),
+}
+
+impl From<surrealdb::Error> for Error {
+    fn from(value: surrealdb::Error) -> Self {
+        match value {
+            surrealdb::Error::QueryTimedout => Error::Timeout,
+            e if is_conflict(&e) => Error::Conflict(e),
+            e => Error::Surrealdb(e),
+        }
+    }
+}
+
+impl Error {
+    pub fn cast_failed<T>(value: &Value) -> Self {
+        Error::CastFailed {
+            key: None,
+            expected: std::any::type_name::<T>(),
+            found: ValueKind::of(value),
+        }
+    }
+
+    /// Prefixes the key path of a `CastFailed` error with `key`, so nested failures report
+    /// paths such as `tags.2`. Other errors are returned unchanged.
+    pub fn at_key(self, key: impl fmt::Display) -> Self {
+        match self {
+            Error::CastFailed { key: inner, expected, found } => Error::CastFailed {
+                key: Some(match inner {
+                    Some(inner) => format!("{key}.{inner}"),
+                    None => key.to_string(),
+                }),
+                expected,
+                found,
+            },
+            e => e,
+        }
+    }
+
+    fn in_statement(self, sql: &str, index: usize) -> Self {
+        Error::Query {
+            sql: sql.to_string(),
+            index,
+            source: Box::new(self),
+        }
+    }
+
+    /// The underlying error with any `Query` context removed, for matching on the cause.
+    pub fn root(&self) -> &Error {
+        match self {
+            Error::Query { source, .. } => source.root(),
+            e => e,
+        }
+    }
+
+    /// The error message with string and number literals in SQL, and in messages reported
+    /// by the datastore, replaced by `?`. Suitable for logs that must not contain user data.
+    pub fn redacted(&self) -> String {
+        match self {
+            Error::Query { sql, index, source } => {
+                format!("statement {index} of `{}` failed: {}", redact_sql(sql), source.redacted())
+            },
+            Error::Conflict(_)|Error::Surrealdb(_)|Error::Json(_) => redact_sql(&self.to_string()),
+            e => e.to_string(),
+        }
+    }
+}
+
+/// The storage engines only report conflicts as a message: RocksDB as "Resource busy",
+/// TiKV and FoundationDB as a write conflict or a request to retry.
+fn is_conflict(e: &surrealdb::Error) -> bool {
+    match e {
+        surrealdb::Error::Tx(msg) => {
+            let msg = msg.to_lowercase();
+            ["conflict", "resource busy", "try again", "retryable"].iter().any(|m| msg.contains(m))
+        },
+        _ => false,
+    }
 }
 
 type Result<T> = std::result::Result<T, Error>;
 
-pub struct Db (Datastore, Session);
+/// A handle to a datastore and the session queries run under. Cloning is cheap and clones
+/// share the underlying datastore and any concurrency limit.
+#[derive(Clone)]
+pub struct Db {
+    ds: Arc<Datastore>,
+    sess: Session,
+    limiter: Option<Arc<Semaphore>>,
+    retry: RetryPolicy,
+    statements: Option<Arc<Mutex<StatementCache>>>,
+    observer: Option<Arc<dyn QueryObserver>>,
+    middleware: Vec<Arc<dyn QueryMiddleware>>,
+    read_only: bool,
+    strict: bool,
+    check_params: bool,
+    #[cfg(feature = "tracing")]
+    slow_query: Option<Duration>,
+}
 
 impl Db {
     pub fn new(ds: Datastore, sess: Session) -> Self {
-        Self(ds, sess)
+        Self {
+            ds: Arc::new(ds),
+            sess,
+            limiter: None,
+            retry: RetryPolicy::NONE,
+            statements: None,
+            observer: None,
+            middleware: Vec::new(),
+            read_only: false,
+            strict: false,
+            check_params: false,
+            #[cfg(feature = "tracing")]
+            slow_query: None,
+        }
+    }
+
+    /// Opens the datastore at `path` (`memory`, `file://..`, `rocksdb://..`, `tikv://..` or
+    /// `fdb://..`, depending on enabled surrealdb features) with a root session using the
+    /// given namespace and database.
+    pub async fn connect(path: &str, ns: &str, db: &str) -> Result<Self> {
+        let ds = Datastore::new(path).await?;
+        Ok(Self::new(ds, Session::for_kv().with_ns(ns).with_db(db)))
+    }
+
+    pub async fn memory(ns: &str, db: &str) -> Result<Self> {
+        Self::connect("memory", ns, db).await
+    }
+
+    /// A handle on the same datastore whose queries run in namespace `ns` and database `db`,
+    /// keeping the session's authentication. Everything else, including the concurrency
+    /// limit, statement cache and observer, is shared with this handle.
+    pub fn scoped(&self, ns: &str, db: &str) -> Self {
+        let mut scoped = self.clone();
+        scoped.sess = scoped.sess.with_ns(ns).with_db(db);
+        scoped
+    }
+
+    /// Allows at most `max` queries to execute at once across this handle and clones made
+    /// from it afterwards; further queries wait for a free slot.
+    pub fn max_concurrent_queries(mut self, max: usize) -> Self {
+        self.limiter = Some(Arc::new(Semaphore::new(max.max(1))));
+        self
+    }
+
+    /// Sets how transactions committed through this handle are retried when they fail with
+    /// `Error::Conflict`. Other errors are always returned straight away.
+    pub fn retry_policy(mut self, policy: RetryPolicy) -> Self {
+        self.retry = policy;
+        self
+    }
+
+    /// Sets whether queries made through this handle, and clones made from it afterwards, run
+    /// in strict mode, where the namespace, database and tables must be defined before they
+    /// are used. `QueryBuilder::strict` overrides it for a single query. Off by default.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    /// Keeps up to `capacity` parsed queries, keyed by their SQL, in a cache shared with clones
+    /// made from this handle afterwards. `query` and `prepare` then parse each distinct SQL
+    /// string once.
+    pub fn statement_cache(mut self, capacity: usize) -> Self {
+        self.statements = Some(Arc::new(Mutex::new(StatementCache::new(capacity))));
+        self
+    }
+
+    fn parse(&self, sql: &str) -> Result<Arc<Query>> {
+        let Some(cache) = &self.statements else {
+            return Ok(Arc::new(surrealdb::sql::parse(sql)?));
+        };
+        if let Some(query) = cache.lock().unwrap_or_else(PoisonError::into_inner).get(sql) {
+            return Ok(query);
+        }
+        let query = Arc::new(surrealdb::sql::parse(sql)?);
+        cache.lock().unwrap_or_else(PoisonError::into_inner).insert(sql, query.clone());
+        Ok(query)
+    }
+
+    /// Reports every round trip to the datastore made through this handle, and clones made
+    /// from it afterwards, to `observer`.
+    pub fn observer(mut self, observer: impl QueryObserver + 'static) -> Self {
+        self.observer = Some(Arc::new(observer));
+        self
     }
 
-    pub fn query(&self, sql: &str) -> QueryBuilder {
+    /// Runs `middleware` around every round trip to the datastore made through this handle,
+    /// and clones made from it afterwards, after any middleware added before it.
+    pub fn middleware(mut self, middleware: impl QueryMiddleware + 'static) -> Self {
+        self.middleware.push(Arc::new(middleware));
+        self
+    }
+
+    /// Emits a warning event for every query that takes at least `threshold`.
+    #[cfg(feature = "tracing")]
+    pub fn slow_query_threshold(mut self, threshold: Duration) -> Self {
+        self.slow_query = Some(threshold);
+        self
+    }
+
+    async fn permit(&self) -> Option<SemaphorePermit<'_>> {
+        match &self.limiter {
+            Some(limiter) => Some(limiter.acquire().await.expect("query limiter is never closed")),
+            None => None,
+        }
+    }
+
+    /// Runs the `before` hooks of the middleware on `sql` and `vars`, returning the SQL to
+    /// run, or `None` without middleware.
+    fn before_hooks(&self, sql: &str, vars: &mut BTreeMap<String, Value>) -> Result<Option<String>> {
+        if self.middleware.is_empty() {
+            return Ok(None);
+        }
+        let mut sql = sql.to_string();
+        for m in &self.middleware {
+            m.before(&mut sql, vars)?;
+        }
+        Ok(Some(sql))
+    }
+
+    fn after_hooks(&self, sql: &str, res: &mut Result<Vec<Response>>) {
+        for m in self.middleware.iter().rev() {
+            m.after(sql, res);
+        }
+    }
+
+    /// Runs `sql` through the middleware and sends it.
+    async fn execute_sql(&self, sql: &str, vars: Option<BTreeMap<String, Value>>, strict: bool) -> Result<Vec<Response>> {
+        let mut vars = vars.unwrap_or_default();
+        let rewritten = self.before_hooks(sql, &mut vars)?;
+        let sql = rewritten.as_deref().unwrap_or(sql);
+        let mut res = self.send_sql(sql, Some(vars), strict).await;
+        self.after_hooks(sql, &mut res);
+        res
+    }
+
+    async fn send_sql(&self, sql: &str, vars: Option<BTreeMap<String, Value>>, strict: bool) -> Result<Vec<Response>> {
+        if self.read_only {
+            let query = self.parse(sql)?;
+            read_only::check(&query, || sql.to_string())?;
+            return self.process_query(Query::clone(&query), vars, strict).await;
+        }
+        let _permit = self.permit().await;
+        QueryTrace::new(self, &sql, vars.as_ref()).run(self.ds.execute(sql, &self.sess, vars, strict)).await
+    }
+
+    async fn process_query(&self, query: Query, vars: Option<BTreeMap<String, Value>>, strict: bool) -> Result<Vec<Response>> {
+        if self.read_only {
+            read_only::check(&query, || query.to_string())?;
+        }
+        let _permit = self.permit().await;
+        let trace = QueryTrace::new(self, &query, vars.as_ref());
+        trace.run(self.ds.process(query, &self.sess, vars, strict)).await
+    }
+
+    /// Starts a query. `sql` may be borrowed or owned, such as the result of `format!`, and
+    /// is only copied when `QueryBuilder::ident` rewrites it.
+    pub fn query<'a>(&'a self, sql: impl Into<Cow<'a, str>>) -> QueryBuilder<'a> {
         QueryBuilder::new(self, sql)
     }
+
+    /// Parses `sql` now so it can be executed repeatedly without parsing it again.
+    pub fn prepare(&self, sql: &str) -> Result<PreparedQuery<'_>> {
+        Ok(PreparedQuery::new(self, sql, self.parse(sql)?))
+    }
+
+    pub fn begin(&self) -> Transaction<'_> {
+        Transaction::new(self)
+    }
+
+    /// Runs `f` against a new transaction and commits the statements it queued. If `f`
+    /// returns an error nothing is sent to the datastore, so no changes are applied.
+    pub async fn transaction<'a, F, Fut>(&'a self, f: F) -> Result<Vec<Vec<Record>>>
+    where
+        F: FnOnce(Transaction<'a>) -> Fut,
+        Fut: Future<Output = Result<()>>,
+    {
+        let tx = self.begin();
+        f(tx.clone()).await?;
+        tx.commit().await
+    }
+
+    /// Runs each query independently, up to `concurrency` at a time, and returns their results
+    /// in the order given. A failing query doesn't stop the others. Queries are not run in a
+    /// transaction, so those that succeed stay applied.
+    pub async fn batch<'a>(&'a self, queries: Vec<QueryBuilder<'a>>, concurrency: usize) -> Vec<Result<Vec<Record>>> {
+        stream::iter(queries.into_iter().map(QueryBuilder::execute))
+            .buffered(concurrency.max(1))
+            .collect()
+            .await
+    }
+
+    pub fn table<T: FromRecord + IntoValue + Clone>(&self, name: &str) -> Table<'_, T> {
+        Table::new(self, name)
+    }
+
+    pub fn select_from(&self, table: &str) -> SelectBuilder<'_> {
+        SelectBuilder::new(self, table)
+    }
+
+    /// Starts a `from->edge->to` graph edge. Call `execute` on the builder to create it.
+    pub fn relate<F: Into<Thing>, T: Into<Thing>>(&self, from: F, edge: &str, to: T) -> RelateBuilder<'_> {
+        RelateBuilder::new(self, from.into(), edge, to.into())
+    }
+
+    /// Creates a record in `table` from `data` and returns it. An `id` field in `data`,
+    /// either a record id or the id part alone, fixes the record id; creating a record that
+    /// already exists fails.
+    pub async fn create<D: IntoValue>(&self, table: &str, data: D) -> Result<Record> {
+        let data = data.into_value();
+        // CONTENT doesn't set the id of a record created from a table name.
+        let query = match data.pick(&["id".into()]) {
+            Value::None|Value::Null => self.query("CREATE type::table($table) CONTENT $data").arg_table("table", table),
+            Value::Thing(thing) => self.query("CREATE $thing CONTENT $data").arg("thing", thing),
+            id => self.query("CREATE type::thing($table, $id) CONTENT $data").arg_table("table", table).arg("id", id),
+        };
+        query.arg("data", data)
+            .execute()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(Error::NotFound)
+    }
+
+    /// Inserts `items` into `table` with one `INSERT` statement per `chunk_size` items, and
+    /// returns the created records in order. An `id` field in an item fixes its record id.
+    /// Chunks run one after another without a transaction, so when one fails the records
+    /// inserted by earlier chunks stay.
+    pub async fn insert_many<D: IntoValue>(&self, table: &str, items: impl IntoIterator<Item = D>, chunk_size: usize) -> Result<Vec<Record>> {
+        let chunk_size = chunk_size.max(1);
+        let mut items = items.into_iter().peekable();
+        let mut records = Vec::new();
+        while items.peek().is_some() {
+            let chunk = self.query("INSERT INTO {table} $items")
+                .ident("table", table)
+                .arg_array("items", items.by_ref().take(chunk_size))
+                .execute()
+                .await?;
+            records.extend(chunk);
+        }
+        Ok(records)
+    }
+
+    pub async fn select<T: Into<Thing>>(&self, thing: T) -> Result<Option<Record>> {
+        Ok(self.query("SELECT * FROM $thing")
+            .arg("thing", thing.into())
+            .execute()
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Replaces the content of `thing`, creating it if it does not exist.
+    pub async fn update<T: Into<Thing>, D: IntoValue>(&self, thing: T, data: D) -> Result<Record> {
+        self.modify(thing.into(), "CONTENT", data.into_value()).await
+    }
+
+    /// Creates `thing` with `data`, or replaces its content if it already exists. This is a
+    /// single UPDATE statement, so concurrent upserts of the same record cannot race into
+    /// a duplicate; it behaves exactly like `update`.
+    pub async fn upsert<T: Into<Thing>, D: IntoValue>(&self, thing: T, data: D) -> Result<Record> {
+        self.update(thing, data).await
+    }
+
+    /// The number of records in `table`, or of those matching a WHERE condition together
+    /// with the arguments its `$params` are bound to. `$table` is taken by the table name.
+    pub async fn count(&self, table: &str, filter: Option<(&str, ArgsBuilder)>) -> Result<u64> {
+        let (cond, args) = match filter {
+            Some((cond, args)) => (format!(" WHERE {cond}"), args),
+            None => Default::default(),
+        };
+        let mut query = self.query(format!("RETURN count((SELECT id FROM type::table($table){cond}))"));
+        query.args = args;
+        query.arg_table("table", table)
+            .execute_scalar()
+            .await
+    }
+
+    pub async fn exists<T: Into<Thing>>(&self, thing: T) -> Result<bool> {
+        Ok(!self.query("SELECT id FROM $thing")
+            .arg("thing", thing.into())
+            .execute()
+            .await?
+            .is_empty())
+    }
+
+    /// Merges `data` into `thing`, creating it if it does not exist.
+    pub async fn merge<T: Into<Thing>, D: IntoValue>(&self, thing: T, data: D) -> Result<Record> {
+        self.modify(thing.into(), "MERGE", data.into_value()).await
+    }
+
+    /// Replaces the content of `thing` with `data` and sets its `version` field to
+    /// `expected + 1`, but only if its `version` is `expected`; a record without one is at
+    /// version 0. Fails with `Error::VersionConflict` when the record changed since it was
+    /// read at `expected`, or doesn't exist. `data` must be an object.
+    pub async fn update_if_version<T: Into<Thing>, D: IntoValue>(&self, thing: T, expected: u64, data: D) -> Result<Record> {
+        let mut data = match data.into_value() {
+            Value::Object(obj) => obj,
+            v => return Err(Error::cast_failed::<Record>(&v)),
+        };
+        data.insert("version".to_string(), (expected + 1).into_value());
+        self.query("UPDATE (SELECT id FROM $thing WHERE version = $version OR (version IS NONE AND $version = 0)) CONTENT $data")
+            .arg("thing", thing.into())
+            .arg("version", expected)
+            .arg("data", data)
+            .execute_first()
+            .await?
+            .ok_or(Error::VersionConflict(expected))
+    }
+
+    /// Writes `record` back to the record named by its `id` field with `UPDATE ... MERGE`, so
+    /// fields set or changed since it was read are stored, and returns the record as stored.
+    /// Fields removed from `record` are left in place. Fails with `Error::InvalidKey` when
+    /// `record` has no record id in `id`.
+    pub async fn save(&self, record: &Record) -> Result<Record> {
+        let mut data = record.clone();
+        let thing: RecordId = data.take("id").map_err(|_e| Error::InvalidKey("id".to_string()))?;
+        self.merge(thing, data).await
+    }
+
+    async fn modify(&self, thing: Thing, clause: &str, data: Value) -> Result<Record> {
+        self.query(format!("UPDATE $thing {clause} $data"))
+            .arg("thing", thing)
+            .arg("data", data)
+            .execute()
+            .await?
+            .into_iter()
+            .next()
+            .ok_or(Error::NotFound)
+    }
+
+    /// Deletes `thing`, returning the record as it was before deletion if it existed.
+    pub async fn delete<T: Into<Thing>>(&self, thing: T) -> Result<Option<Record>> {
+        Ok(self.query("DELETE $thing RETURN BEFORE")
+            .arg("thing", thing.into())
+            .execute()
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Marks `thing` as deleted by setting its `deleted_at` field to the current time, and
+    /// returns the updated record. Records that don't exist or are already marked are left
+    /// alone and yield `None`. `Table::soft_delete` hides marked records from reads.
+    pub async fn soft_delete<T: Into<Thing>>(&self, thing: T) -> Result<Option<Record>> {
+        Ok(self.query("UPDATE (SELECT id FROM $thing WHERE deleted_at IS NONE) SET deleted_at = time::now() RETURN AFTER")
+            .arg("thing", thing.into())
+            .execute()
+            .await?
+            .into_iter()
+            .next())
+    }
+
+    /// Streams every record of `table` cast to `T`, fetching `batch` records per query with
+    /// `QueryBuilder::execute_stream`. The first batch is fetched eagerly so that errors
+    /// surface before streaming starts.
+    pub async fn select_stream_typed<T: FromValue>(&self, table: &str, batch: usize) -> Result<impl Stream<Item = Result<T>> + '_> {
+        let records = self.query("SELECT * FROM type::table($table)")
+            .arg_table("table", table)
+            .execute_stream(batch)
+            .await?;
+        Ok(records.map(|r| r.and_then(|r| Value::Object(r.0).cast())))
+    }
+
+    /// Runs a trivial query and returns its round-trip latency, or `Error::Timeout` if it
+    /// does not complete within `timeout`.
+    pub async fn ping_with_timeout(&self, timeout: Duration) -> Result<Duration> {
+        let start = Instant::now();
+        let res = tokio::time::timeout(timeout, self.send_sql("RETURN true;", None, false))
+            .await
+            .map_err(|_e| Error::Timeout)??;
+        for r in res {
+            r.result?;
+        }
+        Ok(start.elapsed())
+    }
 }
 
-#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Deserialize)]
+/// Serializes as a plain JSON-style map, with record ids rendered as `table:id` strings.
+#[derive(Clone, Debug, Default, Eq, Ord, PartialEq, PartialOrd, Deserialize, Serialize)]
 pub struct Record(Object);
 
 impl Record {
-    pub fn remove(&mut self, k: &'static str) -> Result<Value> {
-        let v = self.0.remove(k).map(|v| v.into()).ok_or(Error::InvalidKey(k))?;
+    fn from_result(v: Value) -> Vec<Record> {
+        match v {
+            Value::Object(obj) => vec![Record(obj)],
+            Value::Array(arr) => arr.into_iter().filter_map(|v| match v {
+                Value::Object(obj) => Some(Record(obj)),
+                _ => None,
+            }).collect(),
+            _ => Default::default(),
+        }
+    }
+
+    pub fn into_json(self) -> Result<serde_json::Value> {
+        Ok(serde_json::to_value(self)?)
+    }
+
+    pub fn remove(&mut self, k: impl AsRef<str>) -> Result<Value> {
+        let k = k.as_ref();
+        let v = self.0.remove(k).ok_or_else(|| Error::InvalidKey(k.to_string()))?;
         Ok(v)
     }
+
+    /// Sets the top-level field `k` to `value`, replacing any value it had.
+    pub fn set<V: IntoValue>(&mut self, k: impl Into<String>, value: V) -> &mut Self {
+        self.0.insert(k.into(), value.into_value());
+        self
+    }
+
+    /// Merges the fields of `other` into this record like `UPDATE ... MERGE`: fields holding
+    /// objects on both sides are merged recursively, and any other field of `other` replaces
+    /// the one here.
+    pub fn merge(&mut self, other: Record) -> &mut Self {
+        merge_object(&mut self.0, other.0);
+        self
+    }
+
+    /// Removes `k` and casts it to `T`. A missing key is cast from NONE, so `Option` targets
+    /// yield `None` while others fail with `InvalidKey`.
+    pub fn take<T: FromValue>(&mut self, k: &str) -> Result<T> {
+        match self.0.remove(k) {
+            Some(v) => v.cast().map_err(|e| e.at_key(k)),
+            None => Value::None.cast().map_err(|_e| Error::InvalidKey(k.to_string())),
+        }
+    }
+
+    /// Looks up a dotted path such as `author.profile.email`, descending into nested objects
+    /// (and arrays, for numeric segments) without consuming the record.
+    pub fn get(&self, path: &str) -> Result<&Value> {
+        let mut parts = path.split('.');
+        let first = parts.next().unwrap_or_default();
+        let mut v = self.0.get(first).ok_or_else(|| Error::InvalidKey(path.to_string()))?;
+        for part in parts {
+            v = match v {
+                Value::Object(obj) => obj.get(part),
+                Value::Array(arr) => part.parse::<usize>().ok().and_then(|i| arr.get(i)),
+                _ => None,
+            }.ok_or_else(|| Error::InvalidKey(path.to_string()))?;
+        }
+        Ok(v)
+    }
+
+    /// Like `take`, but clones the value at a dotted path and leaves the record intact.
+    pub fn get_as<T: FromValue>(&self, path: &str) -> Result<T> {
+        match self.get(path) {
+            Ok(v) => v.clone().cast().map_err(|e| e.at_key(path)),
+            Err(e) => Value::None.cast().map_err(|_e| e),
+        }
+    }
+
+    pub fn get_ci(&self, k: &str) -> Result<&Value> {
+        let key = self.find_ci(k)?;
+        Ok(&self.0[key])
+    }
+
+    pub fn take_ci<T: FromValue>(&mut self, k: &str) -> Result<T> {
+        let key = self.find_ci(k)?.to_owned();
+        self.take(&key)
+    }
+
+    fn find_ci(&self, k: &str) -> Result<&str> {
+        let lower = k.to_lowercase();
+        let mut matches: Vec<&String> = self.0.keys().filter(|key| key.to_lowercase() == lower).collect();
+        match matches.len() {
+            0 => Err(Error::InvalidKey(k.to_string())),
+            1 => Ok(matches.remove(0)),
+            _ => Err(Error::AmbiguousKey(k.to_string(), matches.into_iter().cloned().collect())),
+        }
+    }
+}
+
+fn merge_object(into: &mut Object, from: Object) {
+    for (k, v) in from {
+        match (into.get_mut(&k), v) {
+            (Some(Value::Object(into)), Value::Object(from)) => merge_object(into, from),
+            (_, v) => {
+                into.insert(k, v);
+            },
+        }
+    }
+}
+
+/// The SQL of a `QueryBuilder`, either as given to `Db::query` or shared with the
+/// `PreparedQuery` it was started from, so executing a prepared query doesn't copy it.
+#[derive(Clone)]
+enum Sql<'a> {
+    Text(Cow<'a, str>),
+    Shared(Arc<str>),
+}
+
+impl Deref for Sql<'_> {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        match self {
+            Sql::Text(sql) => sql,
+            Sql::Shared(sql) => sql,
+        }
+    }
 }
 
 pub struct QueryBuilder<'a> {
     db: &'a Db,
-    sql: String,
+    sql: Sql<'a>,
+    parsed: Option<Arc<Query>>,
     args: ArgsBuilder,
+    timeout: Option<Duration>,
+    invalid_ident: Option<String>,
+    strict: bool,
+    intercepted: bool,
 }
 
 impl <'a> QueryBuilder<'a> {
-    pub fn new(db: &'a Db, sql: &str) -> Self {
+    pub fn new(db: &'a Db, sql: impl Into<Cow<'a, str>>) -> Self {
         Self {
             db,
-            sql: sql.to_string(),
+            sql: Sql::Text(sql.into()),
+            parsed: None,
             args: Default::default(),
+            timeout: None,
+            invalid_ident: None,
+            strict: db.strict,
+            intercepted: false,
+        }
+    }
+
+    fn prepared(db: &'a Db, sql: Arc<str>, query: Arc<Query>) -> Self {
+        Self {
+            sql: Sql::Shared(sql),
+            parsed: Some(query),
+            ..Self::new(db, "")
         }
     }
 
-    pub fn arg<S: Into<String>, V: Into<Value>>(mut self, key: S, value: V) -> Self {
+    /// Takes the parsed query, copying it only when it is shared with a `PreparedQuery` or
+    /// the statement cache.
+    fn parse(&mut self) -> Result<Query> {
+        if let Some(name) = &self.invalid_ident {
+            return Err(Error::InvalidIdent(name.clone()));
+        }
+        let query = match self.parsed.take() {
+            Some(query) => query,
+            None => self.db.parse(&self.sql)?,
+        };
+        Ok(Arc::try_unwrap(query).unwrap_or_else(|query| Query::clone(&query)))
+    }
+
+    /// Bounds execution to `timeout`, failing with an error whose `root` is `Error::Timeout`.
+    /// The duration is applied as a TIMEOUT clause to every statement that supports one and
+    /// does not already have one, and the whole execution is also raced against the same
+    /// deadline.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Runs this query in strict mode or not, instead of following the `Db`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    pub fn arg<S: Into<String>, V: IntoValue>(mut self, key: S, value: V) -> Self {
         self.args.arg(key, value);
         self
     }
 
+    /// Replaces every `{key}` in the SQL with `name` checked by `ident`, for table and field
+    /// names, which can't be bound as parameters. An invalid name fails the query with
+    /// `Error::InvalidIdent` when it is executed.
+    pub fn ident(mut self, key: &str, name: &str) -> Self {
+        match ident(name) {
+            Ok(name) => {
+                self.sql = Sql::Text(Cow::Owned(self.sql.replace(&format!("{{{key}}}"), &name)));
+                self.parsed = None;
+            },
+            Err(_e) => {
+                self.invalid_ident.get_or_insert_with(|| name.to_string());
+            },
+        }
+        self
+    }
+
+    /// Binds the table name `name` as `$key`, for `type::table` and `type::thing`. A name
+    /// `ident` rejects fails the query with `Error::InvalidIdent` when it is executed.
+    pub(crate) fn arg_table(mut self, key: &str, name: &str) -> Self {
+        if ident(name).is_err() {
+            self.invalid_ident.get_or_insert_with(|| name.to_string());
+        }
+        self.arg(key, name)
+    }
+
     pub fn sub_args<S: Into<String>>(mut self, key: S, f: impl FnOnce(&mut ArgsBuilder)) -> Self {
         self.args.sub_args(key, f);
         self
     }
 
-    pub async fn execute(self, strict: bool) -> Result<Vec<Record>> {
-        self.db.0.execute(&self.sql, &self.db.1, Some(self.args.0), strict)
+    /// Binds every field of `value` as a parameter of the same name. Fails with
+    /// `Error::CastFailed` unless `value` serializes to an object.
+    pub fn bind_struct<T: Serialize>(mut self, value: &T) -> Result<Self> {
+        match serialize(value)? {
+            Value::Object(obj) => {
+                for (k, v) in obj {
+                    self.args.arg(k, v);
+                }
+                Ok(self)
+            },
+            v => Err(Error::cast_failed::<Record>(&v)),
+        }
+    }
+
+    /// Binds `value` as `$content`, e.g. for `CREATE user CONTENT $content`.
+    pub fn content<T: Serialize>(mut self, value: &T) -> Result<Self> {
+        self.args.arg("content", serialize(value)?);
+        Ok(self)
+    }
+
+    pub fn arg_opt<S: Into<String>, V: IntoValue>(mut self, key: S, value: Option<V>) -> Self {
+        self.args.arg_opt(key, value);
+        self
+    }
+
+    pub fn arg_array<S: Into<String>, V: IntoValue>(mut self, key: S, values: impl IntoIterator<Item = V>) -> Self {
+        self.args.arg_array(key, values);
+        self
+    }
+
+    pub fn sub_array<S: Into<String>>(mut self, key: S, f: impl FnOnce(&mut ArrayBuilder)) -> Self {
+        self.args.sub_array(key, f);
+        self
+    }
+
+    /// Runs the `before` hooks of the `Db`'s middleware, once, on the SQL text and arguments.
+    /// Rewritten SQL is parsed again; otherwise any parsed query is kept.
+    fn intercept(&mut self) -> Result<()> {
+        if let Some(name) = &self.invalid_ident {
+            return Err(Error::InvalidIdent(name.clone()));
+        }
+        if self.intercepted {
+            return Ok(());
+        }
+        self.intercepted = true;
+        if let Some(sql) = self.db.before_hooks(&self.sql, &mut self.args.0)? {
+            if sql != *self.sql {
+                self.sql = Sql::Text(Cow::Owned(sql));
+                self.parsed = None;
+            }
+        }
+        Ok(())
+    }
+
+    /// Runs the query, leaving `sql` in place for error context.
+    async fn run(&mut self) -> Result<Vec<Response>> {
+        if let Some(name) = self.invalid_ident.take() {
+            return Err(Error::InvalidIdent(name));
+        }
+        self.intercept()?;
+        let vars = std::mem::take(&mut self.args.0);
+        if self.db.check_params {
+            params::check(&self.sql, &vars)?;
+        }
+        if self.timeout.is_none() && self.parsed.is_none() && self.db.statements.is_none() {
+            let mut res = self.db.send_sql(&self.sql, Some(vars), self.strict).await;
+            self.db.after_hooks(&self.sql, &mut res);
+            return res;
+        }
+        let query = self.parse()?;
+        Self::process(self.db, &self.sql, query, vars, self.strict, self.timeout).await
+    }
+
+    /// Sends the parsed form of `sql`, with the `Db`'s middleware seeing the outcome.
+    async fn process(db: &Db, sql: &str, query: Query, vars: BTreeMap<String, Value>, strict: bool, timeout: Option<Duration>) -> Result<Vec<Response>> {
+        let mut res = Self::send(db, query, vars, strict, timeout).await;
+        db.after_hooks(sql, &mut res);
+        res
+    }
+
+    async fn send(db: &Db, mut query: Query, vars: BTreeMap<String, Value>, strict: bool, timeout: Option<Duration>) -> Result<Vec<Response>> {
+        let Some(timeout) = timeout else {
+            return db.process_query(query, Some(vars), strict).await;
+        };
+        for stmt in query.0.0.iter_mut() {
+            let stmt_timeout = match stmt {
+                Statement::Select(s) => &mut s.timeout,
+                Statement::Create(s) => &mut s.timeout,
+                Statement::Update(s) => &mut s.timeout,
+                Statement::Relate(s) => &mut s.timeout,
+                Statement::Delete(s) => &mut s.timeout,
+                Statement::Insert(s) => &mut s.timeout,
+                _ => continue,
+            };
+            stmt_timeout.get_or_insert_with(|| Timeout(timeout.into()));
+        }
+        let fut = db.process_query(query, Some(vars), strict);
+        tokio::time::timeout(timeout, fut).await.map_err(|_e| Error::Timeout)?
+    }
+
+    /// The records produced by the first statement. Results that aren't objects, such as
+    /// numbers, strings or NONE, are dropped; use `execute_values` for those.
+    pub async fn execute(self) -> Result<Vec<Record>> {
+        self.execute_value().await.map(Record::from_result)
+    }
+
+    /// The values produced by the first statement, whatever their type. An array result
+    /// yields its elements, NONE yields no values and anything else yields itself.
+    pub async fn execute_values(self) -> Result<Vec<Value>> {
+        Ok(match self.execute_value().await? {
+            Value::Array(arr) => arr.0,
+            Value::None => Vec::new(),
+            v => vec![v],
+        })
+    }
+
+    #[deprecated(note = "set strictness with `QueryBuilder::strict` or `Db::strict` and call `execute`")]
+    pub async fn execute_strict(self, strict: bool) -> Result<Vec<Record>> {
+        self.strict(strict).execute().await
+    }
+
+    async fn execute_value(mut self) -> Result<Value> {
+        self.run()
             .await?
             .into_iter()
             .next()
-            .map(|r| r.result.map(|v| {
-                match v {
-                    Value::Object(obj) => vec![Record(obj)],
-                    Value::Array(arr) => arr.into_iter().filter_map(|v| match v {
-                        Value::Object(obj) => Some(Record(obj)),
-                        _ => None,
-                    }).collect(),
-                    _ => Default::default(),
+            .map(|r| r.result)
+            .unwrap_or(Ok(Value::None))
+            .map_err(|e| Error::from(e).in_statement(&self.sql, 0))
+    }
+
+    /// Casts the single value produced by the first statement, such as the result of
+    /// `RETURN ...` or `SELECT count() FROM user GROUP BY ...`. A one-element array is
+    /// unwrapped, as is an object with a single field; no rows are cast from NONE.
+    pub async fn execute_scalar<T: FromValue>(self) -> Result<T> {
+        let mut value = self.execute_value().await?;
+        if let Value::Array(arr) = &mut value {
+            value = match arr.len() {
+                0 => Value::None,
+                1 => arr.0.remove(0),
+                n => return Err(Error::TooManyRows(n)),
+            };
+        }
+        if let Value::Object(obj) = &mut value {
+            if obj.len() == 1 {
+                let key = obj.keys().next().cloned().unwrap_or_default();
+                return obj.remove(&key).unwrap_or_default().cast().map_err(|e| e.at_key(key));
+            }
+        }
+        value.cast()
+    }
+
+    /// Fails with `Error::NotFound` unless there is exactly one record, or `TooManyRows` if
+    /// there are more.
+    pub async fn execute_one(self) -> Result<Record> {
+        self.execute_optional().await?.ok_or(Error::NotFound)
+    }
+
+    pub async fn execute_first(self) -> Result<Option<Record>> {
+        Ok(self.execute().await?.into_iter().next())
+    }
+
+    /// Like `execute_one`, but no record yields `None`.
+    pub async fn execute_optional(self) -> Result<Option<Record>> {
+        let mut records = self.execute().await?;
+        match records.len() {
+            0 | 1 => Ok(records.pop()),
+            n => Err(Error::TooManyRows(n)),
+        }
+    }
+
+    /// Yields the records of a single SELECT statement, fetching `batch` records per query by
+    /// rewriting its LIMIT and START clauses. Any LIMIT or START already present is honoured.
+    /// Add an ORDER BY if the table may change while streaming, so pages don't overlap.
+    /// Other queries are executed once and their first statement's records are streamed.
+    pub async fn execute_stream(mut self, batch: usize) -> Result<impl Stream<Item = Result<Record>> + 'a> {
+        let batch = batch.max(1);
+        self.intercept()?;
+        let mut query = self.parse()?;
+        let select = match query.0.0.as_mut_slice() {
+            [Statement::Select(s)] => std::mem::take(s),
+            _ => {
+                self.parsed = Some(Arc::new(query));
+                let records = self.execute().await?;
+                return Ok(stream::iter(records.into_iter().map(Ok)).left_stream());
+            },
+        };
+        let Self { db, sql, args, timeout, strict, .. } = self;
+        let offset = select.start.as_ref().map_or(0, |s| s.0);
+        let total = select.limit.as_ref().map(|l| l.0);
+        let fetch = move |fetched: usize| {
+            let mut select = select.clone();
+            let sql = sql.clone();
+            let vars = args.0.clone();
+            async move {
+                let limit = total.map_or(batch, |t| batch.min(t - fetched));
+                if limit == 0 {
+                    return Ok(Vec::new());
                 }
-            }))
-            .unwrap_or(Ok(Default::default()))
-            .map_err(|e| e.into())
+                select.limit = Some(Limit(limit));
+                select.start = Some(Start(offset + fetched));
+                let query = Query(Statements(vec![Statement::Select(select)]));
+                Self::process(db, &sql, query, vars, strict, timeout)
+                    .await?
+                    .into_iter()
+                    .next()
+                    .map(|r| r.result.map(Record::from_result))
+                    .unwrap_or(Ok(Default::default()))
+                    .map_err(|e| Error::from(e).in_statement(&sql, 0))
+            }
+        };
+        let first = fetch(0).await?;
+        let next = (first.len() == batch).then_some(batch);
+        let rest = stream::try_unfold(next, move |fetched| {
+            let page = fetched.map(&fetch);
+            async move {
+                let Some(page) = page else { return Ok(None) };
+                let records = page.await?;
+                let next = (records.len() == batch).then(|| fetched.unwrap_or_default() + batch);
+                Ok::<_, Error>(Some((records, next)))
+            }
+        });
+        Ok(stream::once(async { Ok::<_, Error>(first) })
+            .chain(rest)
+            .map_ok(|records| stream::iter(records.into_iter().map(Ok)))
+            .try_flatten()
+            .right_stream())
+    }
+
+    /// Runs a single SELECT restricted to page `page` (counting from 1) of `per_page` rows,
+    /// together with a count of every row it matches. Any LIMIT or START in the query is
+    /// replaced. Fails with `Error::NotSelect` for other queries.
+    pub async fn paginate(mut self, page: usize, per_page: usize) -> Result<Page<Record>> {
+        if page == 0 {
+            return Err(Error::InvalidPage);
+        }
+        let per_page = per_page.max(1);
+        self.intercept()?;
+        let mut items = match self.parse()?.0.0.as_mut_slice() {
+            [Statement::Select(s)] => std::mem::take(s),
+            _ => return Err(Error::NotSelect),
+        };
+        let mut count = items.clone();
+        count.order = None;
+        count.limit = None;
+        count.start = None;
+        count.fetch = None;
+        if count.group.is_none() && count.split.is_none() {
+            // Only the number of rows matters, so avoid building full documents.
+            if let Some(Statement::Select(ids)) = surrealdb::sql::parse("SELECT id FROM x")?.0.0.pop() {
+                count.expr = ids.expr;
+            }
+        }
+        let count = Value::Function(Box::new(Function::Normal("count".to_string(), vec![
+            Value::Subquery(Box::new(Subquery::Select(count))),
+        ])));
+        let count = Statement::Output(OutputStatement { what: count });
+        // The datastore adds START and LIMIT, so keep their sum in range.
+        let start = (page - 1).saturating_mul(per_page);
+        items.limit = Some(Limit(per_page.min(usize::MAX - start)));
+        items.start = Some(Start(start));
+        let query = Query(Statements(vec![Statement::Select(items), count]));
+        let mut res = Self::process(self.db, &self.sql, query, self.args.0, self.strict, self.timeout).await?.into_iter();
+        let items = match res.next() {
+            Some(r) => Record::from_result(r.result.map_err(|e| Error::from(e).in_statement(&self.sql, 0))?),
+            None => Vec::new(),
+        };
+        let total = match res.next() {
+            Some(r) => {
+                let total = r.result.map_err(|e| Error::from(e).in_statement(&self.sql, 0))?;
+                usize::try_from(i64::from_value(total.clone())?).map_err(|_e| Error::cast_failed::<usize>(&total))?
+            },
+            None => 0,
+        };
+        Ok(Page { items, total, page, per_page })
+    }
+
+    /// Returns one entry per statement. The outer error covers failures that prevent the
+    /// query from running at all, such as parse errors.
+    pub async fn execute_all(mut self) -> Result<Vec<Result<Vec<Record>>>> {
+        Ok(self.run()
+            .await?
+            .into_iter()
+            .enumerate()
+            .map(|(i, r)| r.result.map(Record::from_result).map_err(|e| Error::from(e).in_statement(&self.sql, i)))
+            .collect())
+    }
+
+    /// Runs every statement and fails with the first statement error, discarding results.
+    pub async fn execute_check(self) -> Result<()> {
+        for res in self.execute_all().await? {
+            res?;
+        }
+        Ok(())
+    }
+
+    pub async fn execute_as<T: DeserializeOwned>(self) -> Result<Vec<T>> {
+        self.execute()
+            .await?
+            .into_iter()
+            .map(|r| Ok(serde_json::from_value(r.into_json()?)?))
+            .collect()
+    }
+
+    pub async fn execute_grouped<K: FromValue + Eq + Hash>(self, key_field: &str) -> Result<HashMap<K, Vec<Record>>> {
+        let mut groups: HashMap<K, Vec<Record>> = HashMap::new();
+        for record in self.execute().await? {
+            let key = record.0.get(key_field).cloned().ok_or_else(|| Error::InvalidKey(key_field.to_string()))?
+                .cast()
+                .map_err(|e| e.at_key(key_field))?;
+            groups.entry(key).or_default().push(record);
+        }
+        Ok(groups)
     }
 }
 
@@ -89,8 +1142,8 @@ impl <'a> QueryBuilder<'a> {
 pub struct ArgsBuilder(BTreeMap<String, Value>);
 
 impl ArgsBuilder {
-    pub fn arg<S: Into<String>, V: Into<Value>>(&mut self, key: S, value: V) -> &mut Self {
-        self.0.insert(key.into(), value.into());
+    pub fn arg<S: Into<String>, V: IntoValue>(&mut self, key: S, value: V) -> &mut Self {
+        self.0.insert(key.into(), value.into_value());
         self
     }
 
@@ -100,6 +1153,40 @@ impl ArgsBuilder {
         self.0.insert(key.into(), sub_args.into());
         self
     }
+
+    /// Binds `value` only if it is `Some`; otherwise the key is left untouched. Unbound
+    /// parameters evaluate to NONE in queries.
+    pub fn arg_opt<S: Into<String>, V: IntoValue>(&mut self, key: S, value: Option<V>) -> &mut Self {
+        if let Some(value) = value {
+            self.arg(key, value);
+        }
+        self
+    }
+
+    pub fn arg_array<S: Into<String>, V: IntoValue>(&mut self, key: S, values: impl IntoIterator<Item = V>) -> &mut Self {
+        let values: Vec<Value> = values.into_iter().map(IntoValue::into_value).collect();
+        self.0.insert(key.into(), values.into());
+        self
+    }
+
+    pub fn sub_array<S: Into<String>>(&mut self, key: S, f: impl FnOnce(&mut ArrayBuilder)) -> &mut Self {
+        let mut sub_array = ArrayBuilder::default();
+        f(&mut sub_array);
+        self.0.insert(key.into(), sub_array.into());
+        self
+    }
+}
+
+impl From<Object> for Record {
+    fn from(value: Object) -> Self {
+        Self(value)
+    }
+}
+
+impl From<ArgsBuilder> for Record {
+    fn from(value: ArgsBuilder) -> Self {
+        Self(value.0.into())
+    }
 }
 
 impl From<ArgsBuilder> for Value {
@@ -108,15 +1195,139 @@ impl From<ArgsBuilder> for Value {
     }
 }
 
+#[derive(Debug, Clone, Default)]
+pub struct ArrayBuilder(Vec<Value>);
+
+impl ArrayBuilder {
+    pub fn push<V: IntoValue>(&mut self, value: V) -> &mut Self {
+        self.0.push(value.into_value());
+        self
+    }
+
+    pub fn push_args(&mut self, f: impl FnOnce(&mut ArgsBuilder)) -> &mut Self {
+        let mut args = ArgsBuilder::default();
+        f(&mut args);
+        self.0.push(args.into());
+        self
+    }
+}
+
+impl From<ArrayBuilder> for Value {
+    fn from(value: ArrayBuilder) -> Self {
+        value.0.into()
+    }
+}
+
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub enum ValueKind {
+    None,
+    Null,
+    Bool,
+    Number,
+    Strand,
+    Duration,
+    Datetime,
+    Uuid,
+    Array,
+    Object,
+    Geometry,
+    Thing,
+    Other,
+}
+
+impl ValueKind {
+    pub fn of(value: &Value) -> Self {
+        match value {
+            Value::None => Self::None,
+            Value::Null => Self::Null,
+            Value::True|Value::False => Self::Bool,
+            Value::Number(_) => Self::Number,
+            Value::Strand(_) => Self::Strand,
+            Value::Duration(_) => Self::Duration,
+            Value::Datetime(_) => Self::Datetime,
+            Value::Uuid(_) => Self::Uuid,
+            Value::Array(_) => Self::Array,
+            Value::Object(_) => Self::Object,
+            Value::Geometry(_) => Self::Geometry,
+            Value::Thing(_) => Self::Thing,
+            _ => Self::Other,
+        }
+    }
+}
+
+impl fmt::Display for ValueKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Self::None => "none",
+            Self::Null => "null",
+            Self::Bool => "bool",
+            Self::Number => "number",
+            Self::Strand => "string",
+            Self::Duration => "duration",
+            Self::Datetime => "datetime",
+            Self::Uuid => "uuid",
+            Self::Array => "array",
+            Self::Object => "object",
+            Self::Geometry => "geometry",
+            Self::Thing => "record",
+            Self::Other => "other",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Converts `value` through its JSON form. Strings stay strings, however much they look
+/// like a record id or a datetime.
+fn serialize<T: Serialize>(value: &T) -> Result<Value> {
+    Ok(from_json(serde_json::to_value(value)?))
+}
+
+/// Converts JSON into a `Value` structurally. Unlike `surrealdb::sql::json`, which parses its
+/// input as SurrealQL, this never turns a string into a record link, datetime or other type.
+pub(crate) fn from_json(value: serde_json::Value) -> Value {
+    match value {
+        serde_json::Value::Null => Value::Null,
+        serde_json::Value::Bool(b) => b.into(),
+        serde_json::Value::Number(n) => match (n.as_i64(), n.as_u64()) {
+            (Some(n), _) => n.into(),
+            (None, Some(n)) => n.into(),
+            (None, None) => n.as_f64().unwrap_or_default().into(),
+        },
+        serde_json::Value::String(s) => s.into(),
+        serde_json::Value::Array(arr) => Value::Array(Array(arr.into_iter().map(from_json).collect())),
+        serde_json::Value::Object(obj) => Value::Object(Object(obj.into_iter().map(|(k, v)| (k, from_json(v))).collect())),
+    }
+}
+
+/// Casts a value into a Rust type. For fieldless enums stored as strands,
+/// `#[derive(ValueEnum)]` implements this and `IntoValue` using the variant names, changed by
+/// `#[value(rename_all = "snake_case")]` on the enum (also `lowercase`, `UPPERCASE` and
+/// `SCREAMING_SNAKE_CASE`) or `#[value(rename = "...")]` on a variant.
 pub trait FromValue where Self: Sized {
     fn from_value(value: Value) -> Result<Self>;
 }
 
+/// Builds a type from the fields of a record. `#[derive(FromRecord)]` implements this (and
+/// `FromValue`) by casting each field; a missing key is treated as NONE so `Option` fields
+/// may be absent. Use `#[record(rename = "key")]` when the key differs from the field name.
+pub trait FromRecord where Self: Sized {
+    fn from_record(record: Record) -> Result<Self>;
+}
+
+impl FromValue for Record {
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(obj) => Ok(Record(obj)),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
 impl FromValue for String {
     fn from_value(value: Value) -> Result<Self> {
         match value {
             Value::Strand(_) => Ok(value.as_string()),
-            _ => Err(Error::CastFailed),
+            _ => Err(Error::cast_failed::<Self>(&value)),
         }
     }
 }
@@ -126,7 +1337,7 @@ impl FromValue for bool {
         match value {
             Value::True => Ok(true),
             Value::False => Ok(false),
-            _ => Err(Error::CastFailed),
+            _ => Err(Error::cast_failed::<Self>(&value)),
         }
     }
 }
@@ -135,7 +1346,45 @@ impl FromValue for DateTime<Utc> {
     fn from_value(value: Value) -> Result<Self> {
         match value {
             Value::Datetime(_)|Value::Strand(_) => Ok(value.as_datetime().0),
-            _ => Err(Error::CastFailed),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
+/// Strands may hold either the bare form (`2024-05-01`, `2024-05-01T09:30:00`) or an RFC 3339
+/// datetime, whose local date and time are kept.
+impl FromValue for NaiveDate {
+    fn from_value(value: Value) -> Result<Self> {
+        match &value {
+            Value::Datetime(dt) => Ok(dt.0.date_naive()),
+            Value::Strand(s) => NaiveDate::from_str(s.as_str())
+                .or_else(|_e| DateTime::parse_from_rfc3339(s.as_str()).map(|dt| dt.date_naive()))
+                .map_err(|_e| Error::cast_failed::<Self>(&value)),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
+impl FromValue for NaiveDateTime {
+    fn from_value(value: Value) -> Result<Self> {
+        match &value {
+            Value::Datetime(dt) => Ok(dt.0.naive_utc()),
+            Value::Strand(s) => NaiveDateTime::from_str(s.as_str())
+                .or_else(|_e| DateTime::parse_from_rfc3339(s.as_str()).map(|dt| dt.naive_local()))
+                .map_err(|_e| Error::cast_failed::<Self>(&value)),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
+/// Datetime values are stored in UTC, so they come back with a zero offset; strands keep
+/// the offset they were written with.
+impl FromValue for DateTime<FixedOffset> {
+    fn from_value(value: Value) -> Result<Self> {
+        match &value {
+            Value::Datetime(dt) => Ok(dt.0.into()),
+            Value::Strand(s) => DateTime::parse_from_rfc3339(s.as_str()).map_err(|_e| Error::cast_failed::<Self>(&value)),
+            _ => Err(Error::cast_failed::<Self>(&value)),
         }
     }
 }
@@ -144,16 +1393,36 @@ impl FromValue for i64 {
     fn from_value(value: Value) -> Result<Self> {
         match value {
             Value::Number(_)|Value::Duration(_)|Value::Datetime(_)  => Ok(value.as_int()),
-            _ => Err(Error::CastFailed),
+            _ => Err(Error::cast_failed::<Self>(&value)),
         }
     }
 }
 
+/// Narrower integers are read as `i64` and range checked, so an out of range value is a
+/// `CastFailed` rather than being truncated.
+macro_rules! impl_from_value_checked_int {
+    ($($t:ty),*) => {
+        $(
+            impl FromValue for $t {
+                fn from_value(value: Value) -> Result<Self> {
+                    let n = match value {
+                        Value::Number(_)|Value::Duration(_)|Value::Datetime(_) => value.clone().as_int(),
+                        _ => return Err(Error::cast_failed::<Self>(&value)),
+                    };
+                    <$t>::try_from(n).map_err(|_e| Error::cast_failed::<Self>(&value))
+                }
+            }
+        )*
+    };
+}
+
+impl_from_value_checked_int!(i8, i16, i32, u8, u16, u32, u64, usize);
+
 impl FromValue for f64 {
     fn from_value(value: Value) -> Result<Self> {
         match value {
             Value::Number(_)|Value::Duration(_)|Value::Datetime(_)  => Ok(value.as_float()),
-            _ => Err(Error::CastFailed),
+            _ => Err(Error::cast_failed::<Self>(&value)),
         }
     }
 }
@@ -162,7 +1431,7 @@ impl FromValue for BigDecimal {
     fn from_value(value: Value) -> Result<Self> {
         match value {
             Value::Number(_) => Ok(value.as_decimal()),
-            _ => Err(Error::CastFailed),
+            _ => Err(Error::cast_failed::<Self>(&value)),
         }
     }
 }
@@ -171,23 +1440,115 @@ impl FromValue for Duration {
     fn from_value(value: Value) -> Result<Self> {
         match value {
             Value::Strand(_)|Value::Duration(_) => Ok(value.as_duration().0),
-            _ => Err(Error::CastFailed),
+            _ => Err(Error::cast_failed::<Self>(&value)),
         }
     }
 }
 
+/// A duration that displays in a compact human form such as `1h30m` or `2d4h`, with
+/// sub-second parts rendered as `ms`, `µs` and `ns`.
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct HumanDuration(Duration);
+
+impl HumanDuration {
+    pub fn duration(&self) -> Duration {
+        self.0
+    }
+}
+
+impl From<Duration> for HumanDuration {
+    fn from(value: Duration) -> Self {
+        Self(value)
+    }
+}
+
+impl fmt::Display for HumanDuration {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let secs = self.0.as_secs();
+        let nanos = self.0.subsec_nanos();
+        if secs == 0 && nanos == 0 {
+            return write!(f, "0s");
+        }
+        let parts = [
+            (secs / 86_400, "d"),
+            (secs % 86_400 / 3_600, "h"),
+            (secs % 3_600 / 60, "m"),
+            (secs % 60, "s"),
+            (u64::from(nanos / 1_000_000), "ms"),
+            (u64::from(nanos % 1_000_000 / 1_000), "µs"),
+            (u64::from(nanos % 1_000), "ns"),
+        ];
+        for (n, unit) in parts {
+            if n > 0 {
+                write!(f, "{n}{unit}")?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl FromValue for HumanDuration {
+    fn from_value(value: Value) -> Result<Self> {
+        value.cast().map(Self)
+    }
+}
+
 impl FromValue for Uuid {
     fn from_value(value: Value) -> Result<Self> {
-        let id = match value {
-            Value::Strand(s) => Uuid::from_str(s.as_str()).map_err(|_e| Error::CastFailed)?,
+        let id = match &value {
+            Value::Strand(s) => Uuid::from_str(s.as_str()).map_err(|_e| Error::cast_failed::<Self>(&value))?,
             Value::Uuid(id) => id.0,
-            Value::Thing(t) => Uuid::from_str(&t.id.to_string()).map_err(|_e| Error::CastFailed)?,
-            _ => return Err(Error::CastFailed),
+            Value::Thing(t) => Uuid::from_str(&t.id.to_raw()).map_err(|_e| Error::cast_failed::<Self>(&value))?,
+            _ => return Err(Error::cast_failed::<Self>(&value)),
         };
         Ok(id)
     }
 }
 
+impl <T: FromValue> FromValue for Vec<T> {
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Array(arr) => arr.0.into_iter()
+                .enumerate()
+                .map(|(i, v)| v.cast().map_err(|e| e.at_key(i)))
+                .collect(),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
+impl <T: FromValue> FromValue for Vec<(String, T)> {
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(obj) => obj.0.into_iter()
+                .map(|(k, v)| match v.cast() {
+                    Ok(v) => Ok((k, v)),
+                    Err(e) => Err(e.at_key(k)),
+                })
+                .collect(),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
+impl <T: FromValue> FromValue for BTreeMap<String, T> {
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(_) => Vec::<(String, T)>::from_value(value).map(|entries| entries.into_iter().collect()),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
+impl <T: FromValue, S: BuildHasher + Default> FromValue for HashMap<String, T, S> {
+    fn from_value(value: Value) -> Result<Self> {
+        match value {
+            Value::Object(_) => Vec::<(String, T)>::from_value(value).map(|entries| entries.into_iter().collect()),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
+
 impl <T: FromValue> FromValue for Option<T> {
     fn from_value(value: Value) -> Result<Option<T>> {
         match value {
@@ -205,4 +1566,240 @@ impl ValueCast for Value {
     fn cast<T: FromValue>(self) -> Result<T> {
         T::from_value(self)
     }
-}
\ No newline at end of file
+}
+
+/// Converts a Rust value into a SurrealQL `Value` for binding as a query argument. This is
+/// the counterpart of `FromValue`, covering the types the crate can read back.
+pub trait IntoValue {
+    fn into_value(self) -> Value;
+}
+
+macro_rules! into_value_via_from {
+    ($($t:ty),* $(,)?) => {
+        $(
+            impl IntoValue for $t {
+                fn into_value(self) -> Value {
+                    self.into()
+                }
+            }
+        )*
+    };
+}
+
+into_value_via_from!(
+    Value, Object, Thing, ArgsBuilder, ArrayBuilder,
+    &str, String, bool, DateTime<Utc>, BigDecimal,
+    i8, i16, i32, i64, isize, u8, u16, u32, u64, usize, f32, f64,
+);
+
+impl IntoValue for Duration {
+    fn into_value(self) -> Value {
+        surrealdb::sql::Duration::from(self).into()
+    }
+}
+
+impl IntoValue for HumanDuration {
+    fn into_value(self) -> Value {
+        self.0.into_value()
+    }
+}
+
+/// Stored as a `2024-05-01` strand, the form `FromValue` reads back.
+impl IntoValue for NaiveDate {
+    fn into_value(self) -> Value {
+        self.to_string().into()
+    }
+}
+
+/// Taken to be in UTC.
+impl IntoValue for NaiveDateTime {
+    fn into_value(self) -> Value {
+        Utc.from_utc_datetime(&self).into()
+    }
+}
+
+/// Converted to UTC, so the offset is not kept.
+impl IntoValue for DateTime<FixedOffset> {
+    fn into_value(self) -> Value {
+        self.with_timezone(&Utc).into()
+    }
+}
+
+impl IntoValue for Uuid {
+    fn into_value(self) -> Value {
+        surrealdb::sql::Uuid(self).into()
+    }
+}
+
+impl IntoValue for Record {
+    fn into_value(self) -> Value {
+        self.0.into()
+    }
+}
+
+impl <T: IntoValue> IntoValue for Option<T> {
+    fn into_value(self) -> Value {
+        match self {
+            Some(v) => v.into_value(),
+            None => Value::None,
+        }
+    }
+}
+
+impl <T: IntoValue> IntoValue for Vec<T> {
+    fn into_value(self) -> Value {
+        self.into_iter().map(IntoValue::into_value).collect::<Vec<_>>().into()
+    }
+}
+
+impl <T: IntoValue> IntoValue for BTreeMap<String, T> {
+    fn into_value(self) -> Value {
+        self.into_iter().map(|(k, v)| (k, v.into_value())).collect::<BTreeMap<_, _>>().into()
+    }
+}
+
+impl <T: IntoValue, S: BuildHasher> IntoValue for HashMap<String, T, S> {
+    fn into_value(self) -> Value {
+        self.into_iter().map(|(k, v)| (k, v.into_value())).collect::<BTreeMap<_, _>>().into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn paginate_counts_queries_with_quotes() {
+        let db = Db::memory("test", "paginate").await.unwrap();
+        for _ in 0..3 {
+            db.query("CREATE user SET name = 'a\"b'").execute_check().await.unwrap();
+        }
+        let page = db.query("SELECT * FROM user WHERE name = 'a\"b'")
+            .timeout(Duration::from_secs(5))
+            .paginate(2, 2)
+            .await
+            .unwrap();
+        assert_eq!((page.items.len(), page.total), (1, 3));
+        assert!(matches!(db.query("SELECT * FROM user").paginate(0, 2).await, Err(Error::InvalidPage)));
+        for (page, per_page) in [(usize::MAX, 2), (2, usize::MAX)] {
+            let far = db.query("SELECT * FROM user").paginate(page, per_page).await.unwrap();
+            assert_eq!((far.items.len(), far.total), (0, 3));
+        }
+    }
+
+    #[tokio::test]
+    async fn count_binds_filter_args() {
+        let db = Db::memory("test", "count").await.unwrap();
+        for age in [20, 30, 40] {
+            db.query("CREATE user SET age = $age").arg("age", age).execute_check().await.unwrap();
+        }
+        assert_eq!(db.count("user", None).await.unwrap(), 3);
+        let mut args = ArgsBuilder::default();
+        args.arg("min", 25).arg("table", "ignored");
+        assert_eq!(db.count("user", Some(("age > $min", args))).await.unwrap(), 2);
+    }
+
+    #[test]
+    fn chrono_values_round_trip() {
+        let date = NaiveDate::from_ymd_opt(2024, 5, 1).unwrap();
+        let time = date.and_hms_opt(9, 30, 0).unwrap();
+        let offset = DateTime::parse_from_rfc3339("2024-05-01T09:30:00+02:00").unwrap();
+        assert_eq!(NaiveDate::from_value(date.into_value()).unwrap(), date);
+        assert_eq!(NaiveDateTime::from_value(time.into_value()).unwrap(), time);
+        assert_eq!(DateTime::<FixedOffset>::from_value(offset.into_value()).unwrap(), offset);
+        assert_eq!(offset.into_value(), Utc.with_ymd_and_hms(2024, 5, 1, 7, 30, 0).unwrap().into_value());
+    }
+
+    #[test]
+    fn map_cast_errors_name_the_map() {
+        let err = BTreeMap::<String, i64>::from_value(Value::from("x")).unwrap_err();
+        assert!(matches!(err, Error::CastFailed { expected, .. } if expected == std::any::type_name::<BTreeMap<String, i64>>()));
+        let err = HashMap::<String, i64>::from_value(Value::from("x")).unwrap_err();
+        assert!(matches!(err, Error::CastFailed { expected, .. } if expected == std::any::type_name::<HashMap<String, i64>>()));
+    }
+
+    #[tokio::test]
+    async fn grouped_cast_errors_name_the_key() {
+        let db = Db::memory("test", "grouped").await.unwrap();
+        db.query("CREATE user SET team = 'red'").execute_check().await.unwrap();
+        let err = db.query("SELECT * FROM user").execute_grouped::<i64>("team").await.unwrap_err();
+        assert!(matches!(err, Error::CastFailed { key: Some(key), .. } if key == "team"));
+    }
+
+    #[tokio::test]
+    async fn select_stream_typed_casts_every_batch() {
+        let db = Db::memory("test", "stream").await.unwrap();
+        for n in 0..5 {
+            db.query("CREATE user SET n = $n").arg("n", n).execute_check().await.unwrap();
+        }
+        let mut ns: Vec<i64> = db.select_stream_typed::<Record>("user", 2)
+            .await
+            .unwrap()
+            .map_ok(|r| r.get_as::<i64>("n").unwrap())
+            .try_collect()
+            .await
+            .unwrap();
+        ns.sort();
+        assert_eq!(ns, [0, 1, 2, 3, 4]);
+    }
+
+    #[tokio::test]
+    async fn content_keeps_strings() {
+        let db = Db::memory("test", "content").await.unwrap();
+        let data = BTreeMap::from([("link", "re:hello"), ("date", "2024-05-01"), ("time", "2024-05-01T09:30:00Z")]);
+        let record = db.query("CREATE user:1 CONTENT $content").content(&data).unwrap().execute_one().await.unwrap();
+        for (k, v) in &data {
+            assert_eq!(record.get_as::<String>(k).unwrap(), *v);
+        }
+        let record = db.query("UPDATE user:1 SET link = $link, date = $date").bind_struct(&data).unwrap().execute_one().await.unwrap();
+        assert_eq!((record.get_as::<String>("link").unwrap(), record.get_as::<String>("date").unwrap()), ("re:hello".into(), "2024-05-01".into()));
+    }
+
+    #[tokio::test]
+    async fn update_if_version_checks_the_version() {
+        let db = Db::memory("test", "version").await.unwrap();
+        db.query("CREATE user:a SET name = 'a'").execute_check().await.unwrap();
+        let thing = RecordId::new("user", "a");
+        let mut data = ArgsBuilder::default();
+        data.arg("name", "b");
+        let record = db.update_if_version(thing.clone(), 0, data.clone()).await.unwrap();
+        assert_eq!((record.get_as::<String>("name").unwrap(), record.get_as::<u64>("version").unwrap()), ("b".to_string(), 1));
+        assert!(matches!(db.update_if_version(thing.clone(), 0, data.clone()).await, Err(Error::VersionConflict(0))));
+        let record = db.select(thing.clone()).await.unwrap().unwrap();
+        assert_eq!(record.get_as::<u64>("version").unwrap(), 1);
+        assert_eq!(db.update_if_version(thing, 1, data.clone()).await.unwrap().get_as::<u64>("version").unwrap(), 2);
+        let missing = RecordId::new("user", "missing");
+        assert!(matches!(db.update_if_version(missing.clone(), 0, data).await, Err(Error::VersionConflict(0))));
+        assert!(db.select(missing).await.unwrap().is_none());
+    }
+
+    #[tokio::test]
+    async fn create_keeps_given_id() {
+        let db = Db::memory("test", "create").await.unwrap();
+        let mut data = ArgsBuilder::default();
+        data.arg("id", "fixed").arg("name", "a");
+        let record = db.create("user", data.clone()).await.unwrap();
+        assert_eq!(record.get_as::<RecordId>("id").unwrap(), RecordId::new("user", "fixed"));
+        assert!(db.create("user", data).await.is_err());
+        let mut data = ArgsBuilder::default();
+        data.arg("id", RecordId::new("user", 7));
+        assert_eq!(db.create("user", data).await.unwrap().get_as::<RecordId>("id").unwrap(), RecordId::new("user", 7));
+        assert_eq!(db.count("user", None).await.unwrap(), 2);
+    }
+
+    #[test]
+    fn human_duration_formats() {
+        let cases = [
+            (Duration::ZERO, "0s"),
+            (Duration::from_nanos(1_500_250), "1ms500µs250ns"),
+            (Duration::from_millis(250), "250ms"),
+            (Duration::from_secs(2 * 86_400 + 3 * 3_600 + 5), "2d3h5s"),
+            (Duration::new(90, 7), "1m30s7ns"),
+        ];
+        for (duration, text) in cases {
+            assert_eq!(HumanDuration::from(duration).to_string(), text);
+        }
+        let value = Value::Duration(Duration::from_secs(5400).into());
+        assert_eq!(HumanDuration::from_value(value).unwrap().to_string(), "1h30m");
+    }
+}
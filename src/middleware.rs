@@ -0,0 +1,64 @@
+use std::collections::BTreeMap;
+
+use surrealdb::{Response, sql::Value};
+
+use crate::Result;
+
+/// Hooks around the queries sent to the datastore, registered with `Db::middleware`. Each
+/// one sees the query as the SQL text it was built from, after any rewriting by those
+/// registered before it, and `after` runs in reverse order of registration. `before` runs
+/// once per query and `after` once per round trip, so once per batch of
+/// `QueryBuilder::execute_stream`. Both methods default to doing nothing.
+pub trait QueryMiddleware: Send + Sync {
+    /// Inspects or rewrites the SQL and variables of a query before it runs. Returning an
+    /// error fails the query without sending it.
+    fn before(&self, _sql: &mut String, _vars: &mut BTreeMap<String, Value>) -> Result<()> {
+        Ok(())
+    }
+
+    /// Inspects or replaces the outcome of the query `sql`, with one response per statement.
+    fn after(&self, _sql: &str, _result: &mut Result<Vec<Response>>) {}
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{sync::{Arc, Mutex}, time::Duration};
+
+    use super::*;
+    use crate::Db;
+
+    struct Rename(Arc<Mutex<Vec<String>>>);
+
+    impl QueryMiddleware for Rename {
+        fn before(&self, sql: &mut String, _vars: &mut BTreeMap<String, Value>) -> Result<()> {
+            *sql = sql.replace("person", "user");
+            Ok(())
+        }
+
+        fn after(&self, sql: &str, _result: &mut Result<Vec<Response>>) {
+            self.0.lock().unwrap().push(sql.to_string());
+        }
+    }
+
+    #[tokio::test]
+    async fn middleware_sees_original_sql() {
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        let db = Db::memory("test", "middleware").await.unwrap()
+            .statement_cache(8)
+            .middleware(Rename(seen.clone()));
+        let sql = "CREATE person SET name = 'a\"b'";
+        db.query(sql).timeout(Duration::from_secs(5)).execute_check().await.unwrap();
+        db.query(sql).execute_check().await.unwrap();
+        let records = db.query("SELECT * FROM person WHERE name = 'a\"b'")
+            .timeout(Duration::from_secs(5))
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(*seen.lock().unwrap(), [
+            "CREATE user SET name = 'a\"b'",
+            "CREATE user SET name = 'a\"b'",
+            "SELECT * FROM user WHERE name = 'a\"b'",
+        ]);
+    }
+}
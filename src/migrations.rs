@@ -0,0 +1,85 @@
+use std::{collections::BTreeSet, fs, path::Path};
+
+use crate::{Db, Result};
+
+/// A schema change applied at most once per database. Applied versions are recorded in the
+/// `_migrations` table.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Migration {
+    pub version: u64,
+    pub name: String,
+    pub sql: String,
+}
+
+impl Migration {
+    pub fn new(version: u64, name: impl Into<String>, sql: impl Into<String>) -> Self {
+        Self {
+            version,
+            name: name.into(),
+            sql: sql.into(),
+        }
+    }
+
+    /// Loads every `<version>_<name>.surql` file in `dir`, such as `0001_create_users.surql`,
+    /// sorted by version. Other files are ignored.
+    pub fn from_dir(dir: impl AsRef<Path>) -> Result<Vec<Self>> {
+        let mut migrations = Vec::new();
+        for entry in fs::read_dir(dir)? {
+            let path = entry?.path();
+            if path.extension().is_none_or(|ext| ext != "surql") {
+                continue;
+            }
+            let Some(stem) = path.file_stem().and_then(|s| s.to_str()) else { continue };
+            let (version, name) = stem.split_once('_').unwrap_or((stem, ""));
+            let Ok(version) = version.parse() else { continue };
+            migrations.push(Self::new(version, name, fs::read_to_string(&path)?));
+        }
+        migrations.sort_by_key(|m| m.version);
+        Ok(migrations)
+    }
+}
+
+impl Db {
+    /// Applies the migrations whose version is not yet recorded, in version order, each in
+    /// its own transaction together with its `_migrations` entry. Returns the versions that
+    /// were applied. A failing migration stops the run; earlier ones stay applied.
+    pub async fn migrate(&self, migrations: &[Migration]) -> Result<Vec<u64>> {
+        let applied: BTreeSet<u64> = self.query("SELECT version FROM _migrations")
+            .execute()
+            .await?
+            .into_iter()
+            .map(|mut r| r.take::<u64>("version"))
+            .collect::<Result<_>>()?;
+        let mut pending: Vec<_> = migrations.iter().filter(|m| !applied.contains(&m.version)).collect();
+        pending.sort_by_key(|m| m.version);
+        let mut ran = Vec::with_capacity(pending.len());
+        for m in pending {
+            let tx = self.begin();
+            tx.query(&m.sql);
+            tx.query("CREATE type::thing('_migrations', $version) SET version = $version, name = $name, applied_at = time::now()")
+                .arg("version", m.version)
+                .arg("name", m.name.as_str());
+            tx.commit().await?;
+            ran.push(m.version);
+        }
+        Ok(ran)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn rerunning_is_a_no_op() {
+        let db = Db::memory("test", "migrations").await.unwrap();
+        let migrations = [
+            Migration::new(2, "add_admin", "CREATE user:admin SET name = 'admin'"),
+            Migration::new(1, "create_users", "DEFINE TABLE user SCHEMALESS"),
+        ];
+        assert_eq!(db.migrate(&migrations).await.unwrap(), [1, 2]);
+        assert_eq!(db.migrate(&migrations).await.unwrap(), Vec::<u64>::new());
+        assert_eq!(db.count("user", None).await.unwrap(), 1);
+        assert_eq!(db.count("_migrations", None).await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,67 @@
+use std::{future::{self, Future}, sync::{Arc, Mutex, PoisonError}};
+
+use crate::{ArgsBuilder, DbClient, Error, Record, Result};
+
+type Respond = Arc<dyn Fn(&ArgsBuilder) -> Result<Vec<Record>> + Send + Sync>;
+
+/// A `DbClient` that answers from canned responses instead of a datastore. A query is
+/// matched against each pattern in the order they were added and gets the first match's
+/// response; patterns match when the query contains them, ignoring differences in
+/// whitespace. Unmatched queries return no records.
+///
+/// Every call is recorded and can be inspected with `calls`.
+#[derive(Clone, Default)]
+pub struct MockDb {
+    rules: Vec<(String, Respond)>,
+    calls: Arc<Mutex<Vec<(String, ArgsBuilder)>>>,
+}
+
+fn normalize(sql: &str) -> String {
+    sql.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+impl MockDb {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn on(self, pattern: &str, records: Vec<Record>) -> Self {
+        self.on_with(pattern, move |_args| Ok(records.clone()))
+    }
+
+    /// Fails matching statements with the error returned by `error`.
+    pub fn on_error(self, pattern: &str, error: impl Fn() -> Error + Send + Sync + 'static) -> Self {
+        self.on_with(pattern, move |_args| Err(error()))
+    }
+
+    /// Builds the response from the arguments the query was run with.
+    pub fn on_with(mut self, pattern: &str, respond: impl Fn(&ArgsBuilder) -> Result<Vec<Record>> + Send + Sync + 'static) -> Self {
+        self.rules.push((normalize(pattern), Arc::new(respond)));
+        self
+    }
+
+    /// The SQL and arguments of every statement run so far, in order. Transactions record
+    /// one entry per statement, each with the shared arguments.
+    pub fn calls(&self) -> Vec<(String, ArgsBuilder)> {
+        self.calls.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    fn respond(&self, sql: &str, args: &ArgsBuilder) -> Result<Vec<Record>> {
+        self.calls.lock().unwrap_or_else(PoisonError::into_inner).push((sql.to_string(), args.clone()));
+        let sql = normalize(sql);
+        match self.rules.iter().find(|(pattern, _)| sql.contains(pattern.as_str())) {
+            Some((_, respond)) => respond(args),
+            None => Ok(Vec::new()),
+        }
+    }
+}
+
+impl DbClient for MockDb {
+    fn execute_all(&self, sql: &str, args: ArgsBuilder, _strict: bool) -> impl Future<Output = Result<Vec<Result<Vec<Record>>>>> + Send {
+        future::ready(Ok(vec![self.respond(sql, &args)]))
+    }
+
+    fn transaction(&self, statements: &[&str], args: ArgsBuilder, _strict: bool) -> impl Future<Output = Result<Vec<Vec<Record>>>> + Send {
+        future::ready(statements.iter().map(|stmt| self.respond(stmt, &args)).collect())
+    }
+}
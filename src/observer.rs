@@ -0,0 +1,26 @@
+use std::time::Duration;
+
+use crate::Error;
+
+/// Callbacks for every round trip to the datastore, registered with `Db::observer`. All
+/// methods default to doing nothing, so implementations only override what they record.
+/// They run inline on the query's task and should return quickly.
+pub trait QueryObserver: Send + Sync {
+    fn on_start(&self, _sql: &str) {}
+
+    /// The datastore answered. Individual statements may still have failed; see
+    /// `QueryStats::failed`.
+    fn on_finish(&self, _sql: &str, _stats: &QueryStats) {}
+
+    /// The query could not be run at all, e.g. because it failed to parse or timed out.
+    fn on_error(&self, _sql: &str, _elapsed: Duration, _error: &Error) {}
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct QueryStats {
+    pub elapsed: Duration,
+    /// Records returned across all statements; a scalar result counts as one row.
+    pub rows: usize,
+    pub statements: usize,
+    pub failed: usize,
+}
@@ -0,0 +1,28 @@
+/// One page of a paginated query. Pages are numbered from 1.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page<T> {
+    pub items: Vec<T>,
+    /// Rows matched by the query across all pages.
+    pub total: usize,
+    pub page: usize,
+    pub per_page: usize,
+}
+
+impl <T> Page<T> {
+    pub fn total_pages(&self) -> usize {
+        self.total.div_ceil(self.per_page.max(1))
+    }
+
+    pub fn has_next(&self) -> bool {
+        self.page < self.total_pages()
+    }
+
+    pub fn map<U>(self, f: impl FnMut(T) -> U) -> Page<U> {
+        Page {
+            items: self.items.into_iter().map(f).collect(),
+            total: self.total,
+            page: self.page,
+            per_page: self.per_page,
+        }
+    }
+}
@@ -0,0 +1,43 @@
+use std::collections::BTreeMap;
+
+use surrealdb::sql::Value;
+
+use surrealdb_util_params::{params, BUILTIN};
+
+use crate::{Db, Error, Result};
+
+impl Db {
+    /// Checks the `$params` of every query and transaction against its bindings before
+    /// sending it, failing with `Error::MissingParam` for one that is neither bound, assigned
+    /// with LET nor provided by the datastore. Parameters meant to be NONE, such as those
+    /// skipped by `arg_opt`, must then be bound to `Value::None`. With the `tracing` feature,
+    /// bindings the query never uses are logged as warnings.
+    pub fn check_params(mut self) -> Self {
+        self.check_params = true;
+        self
+    }
+}
+
+pub(crate) fn check(sql: &str, vars: &BTreeMap<String, Value>) -> Result<()> {
+    let (used, defined) = params(sql);
+    if let Some(name) = used.iter().find(|name| !vars.contains_key(*name) && !defined.contains(*name) && !BUILTIN.contains(&name.as_str())) {
+        return Err(Error::MissingParam(name.clone()));
+    }
+    #[cfg(feature = "tracing")]
+    for name in vars.keys().filter(|name| !used.contains(*name)) {
+        tracing::warn!(sql, param = %name, "unused query parameter");
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn check_reports_missing_params() {
+        let vars = BTreeMap::from([("id".to_string(), Value::from(1))]);
+        assert!(check("SELECT * FROM user WHERE id = $id AND $auth", &vars).is_ok());
+        assert!(matches!(check("SELECT * FROM $table", &vars), Err(Error::MissingParam(name)) if name == "table"));
+    }
+}
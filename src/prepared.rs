@@ -0,0 +1,91 @@
+use std::{collections::HashMap, sync::Arc};
+
+use surrealdb::sql::Query;
+
+use crate::{ArgsBuilder, Db, QueryBuilder};
+
+/// SQL parsed once by `Db::prepare`. Each call to `query` or `bind` starts a fresh
+/// `QueryBuilder` that reuses the parsed statements.
+#[derive(Clone)]
+pub struct PreparedQuery<'a> {
+    db: &'a Db,
+    sql: Arc<str>,
+    query: Arc<Query>,
+}
+
+impl <'a> PreparedQuery<'a> {
+    pub(crate) fn new(db: &'a Db, sql: &str, query: Arc<Query>) -> Self {
+        Self {
+            db,
+            sql: sql.into(),
+            query,
+        }
+    }
+
+    pub fn sql(&self) -> &str {
+        &self.sql
+    }
+
+    pub fn query(&self) -> QueryBuilder<'a> {
+        QueryBuilder::prepared(self.db, self.sql.clone(), self.query.clone())
+    }
+
+    pub fn bind(&self, args: ArgsBuilder) -> QueryBuilder<'a> {
+        let mut query = self.query();
+        query.args = args;
+        query
+    }
+}
+
+/// Parsed queries keyed by their SQL, evicting the least recently used entry when full.
+pub(crate) struct StatementCache {
+    capacity: usize,
+    tick: u64,
+    entries: HashMap<String, (Arc<Query>, u64)>,
+}
+
+impl StatementCache {
+    pub(crate) fn new(capacity: usize) -> Self {
+        Self {
+            capacity: capacity.max(1),
+            tick: 0,
+            entries: HashMap::new(),
+        }
+    }
+
+    pub(crate) fn get(&mut self, sql: &str) -> Option<Arc<Query>> {
+        self.tick += 1;
+        let (query, used) = self.entries.get_mut(sql)?;
+        *used = self.tick;
+        Some(query.clone())
+    }
+
+    pub(crate) fn insert(&mut self, sql: &str, query: Arc<Query>) {
+        if self.entries.len() >= self.capacity && !self.entries.contains_key(sql) {
+            let oldest = self.entries.iter().min_by_key(|(_, (_, used))| *used).map(|(k, _)| k.clone());
+            if let Some(oldest) = oldest {
+                self.entries.remove(&oldest);
+            }
+        }
+        self.tick += 1;
+        self.entries.insert(sql.to_string(), (query, self.tick));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Sql;
+
+    #[tokio::test]
+    async fn queries_share_the_sql() {
+        let db = Db::memory("test", "prepared").await.unwrap();
+        let prepared = db.prepare("CREATE user SET n = $n").unwrap();
+        let query = prepared.query();
+        assert!(matches!(&query.sql, Sql::Shared(sql) if Arc::ptr_eq(sql, &prepared.sql)));
+        for n in 0..2 {
+            prepared.query().arg("n", n).execute_check().await.unwrap();
+        }
+        assert_eq!(db.count("user", None).await.unwrap(), 2);
+    }
+}
@@ -0,0 +1,95 @@
+use std::fmt::{self, Write};
+
+use surrealdb::sql::Value;
+
+use crate::Record;
+
+const INDENT: &str = "    ";
+
+/// Quotes keys the same way as the engine's own object output.
+fn key(k: &str) -> String {
+    match !k.is_empty() && k.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        true => k.to_string(),
+        false => format!("{k:?}"),
+    }
+}
+
+fn write_value(out: &mut String, value: &Value, depth: usize) {
+    match value {
+        Value::Object(obj) if !obj.is_empty() => {
+            out.push_str("{\n");
+            for (i, (k, v)) in obj.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&INDENT.repeat(depth + 1));
+                let _ = write!(out, "{}: ", key(k));
+                write_value(out, v, depth + 1);
+            }
+            let _ = write!(out, "\n{}}}", INDENT.repeat(depth));
+        },
+        Value::Array(arr) if !arr.is_empty() => {
+            out.push_str("[\n");
+            for (i, v) in arr.iter().enumerate() {
+                if i > 0 {
+                    out.push_str(",\n");
+                }
+                out.push_str(&INDENT.repeat(depth + 1));
+                write_value(out, v, depth + 1);
+            }
+            let _ = write!(out, "\n{}]", INDENT.repeat(depth));
+        },
+        Value::Object(_) => out.push_str("{}"),
+        Value::Array(_) => out.push_str("[]"),
+        v => {
+            let _ = write!(out, "{v}");
+        },
+    }
+}
+
+impl Record {
+    /// The record as indented SurrealQL, one field per line with keys in sorted order.
+    pub fn pretty(&self) -> String {
+        let mut out = String::new();
+        write_value(&mut out, &Value::Object(self.0.clone()), 0);
+        out
+    }
+
+    /// Formats `records` as a text table with a column for every top-level key, sorted by
+    /// name. Nested values are shown on one line and missing keys are left blank.
+    pub fn format_table(records: &[Record]) -> String {
+        let mut columns: Vec<&String> = records.iter().flat_map(|r| r.0.keys()).collect();
+        columns.sort();
+        columns.dedup();
+        let rows: Vec<Vec<String>> = records.iter()
+            .map(|r| columns.iter().map(|c| r.0.get(*c).map(|v| v.to_string()).unwrap_or_default()).collect())
+            .collect();
+        let header: Vec<String> = columns.iter().map(|c| c.to_string()).collect();
+        let widths: Vec<usize> = header.iter()
+            .enumerate()
+            .map(|(i, h)| rows.iter().map(|r| r[i].chars().count()).chain([h.chars().count()]).max().unwrap_or(0))
+            .collect();
+
+        let mut out = String::new();
+        let mut line = |cells: &[String]| {
+            let cells: Vec<String> = cells.iter().zip(&widths).map(|(c, w)| format!("{c:<w$}")).collect();
+            let _ = writeln!(out, "{}", cells.join(" | ").trim_end());
+        };
+        line(&header);
+        line(&widths.iter().map(|w| "-".repeat(*w)).collect::<Vec<_>>());
+        for row in &rows {
+            line(row);
+        }
+        out
+    }
+}
+
+/// Compact SurrealQL on one line; the alternate form (`{:#}`) is `Record::pretty`.
+impl fmt::Display for Record {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match f.alternate() {
+            true => f.write_str(&self.pretty()),
+            false => write!(f, "{}", self.0),
+        }
+    }
+}
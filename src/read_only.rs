@@ -0,0 +1,94 @@
+use surrealdb::sql::{Field, Function, Part, Query, Statement, Subquery, Value, statements::{IfelseStatement, SelectStatement}};
+
+use crate::{Db, Error, Result};
+
+impl Db {
+    /// A handle on the same datastore that refuses to run any query that could write.
+    /// Queries are checked after parsing and before anything runs: statements other than
+    /// SELECT, RETURN, LET, IF, INFO, USE and transaction control, and subqueries other than
+    /// SELECT anywhere within them, fail with `Error::ReadOnly`.
+    pub fn read_only(&self) -> Self {
+        let mut read_only = self.clone();
+        read_only.read_only = true;
+        read_only
+    }
+}
+
+/// Fails with `Error::ReadOnly`, in the context of the offending statement of `sql`, when
+/// `query` has a statement that could write.
+pub(crate) fn check(query: &Query, sql: impl FnOnce() -> String) -> Result<()> {
+    match query.0.0.iter().position(writes) {
+        Some(i) => Err(Error::ReadOnly.in_statement(&sql(), i)),
+        None => Ok(()),
+    }
+}
+
+fn writes(stmt: &Statement) -> bool {
+    match stmt {
+        Statement::Use(_)|Statement::Info(_)|Statement::Begin(_)|Statement::Cancel(_)|Statement::Commit(_) => false,
+        Statement::Set(s) => value_writes(&s.what),
+        Statement::Output(s) => value_writes(&s.what),
+        Statement::Ifelse(s) => ifelse_writes(s),
+        Statement::Select(s) => select_writes(s),
+        _ => true,
+    }
+}
+
+fn select_writes(s: &SelectStatement) -> bool {
+    s.expr.0.iter().any(|f| match f {
+        Field::All => false,
+        Field::Alone(v)|Field::Alias(v, _) => value_writes(v),
+    })
+        || s.what.0.iter().any(value_writes)
+        || s.cond.as_ref().is_some_and(|c| value_writes(&c.0))
+}
+
+fn ifelse_writes(s: &IfelseStatement) -> bool {
+    s.exprs.iter().any(|(c, v)| value_writes(c) || value_writes(v)) || s.close.as_ref().is_some_and(value_writes)
+}
+
+fn value_writes(value: &Value) -> bool {
+    match value {
+        Value::Subquery(sq) => match sq.as_ref() {
+            Subquery::Value(v) => value_writes(v),
+            Subquery::Select(s) => select_writes(s),
+            Subquery::Ifelse(s) => ifelse_writes(s),
+            _ => true,
+        },
+        Value::Array(arr) => arr.iter().any(value_writes),
+        Value::Object(obj) => obj.values().any(value_writes),
+        Value::Expression(e) => value_writes(&e.l) || value_writes(&e.r),
+        Value::Function(f) => match f.as_ref() {
+            Function::Future(v)|Function::Cast(_, v) => value_writes(v),
+            Function::Normal(_, args)|Function::Script(_, args) => args.iter().any(value_writes),
+        },
+        Value::Idiom(idiom) => idiom.0.iter().any(|part| match part {
+            Part::Where(v) => value_writes(v),
+            Part::Graph(g) => g.cond.as_ref().is_some_and(|c| value_writes(&c.0)),
+            _ => false,
+        }),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn writes_sql(sql: &str) -> bool {
+        surrealdb::sql::parse(sql).unwrap().0.0.iter().any(writes)
+    }
+
+    #[test]
+    fn finds_writes() {
+        for sql in ["SELECT * FROM user WHERE age > 3", "INFO FOR DB", "LET $x = (SELECT * FROM user)", "BEGIN; RETURN 1; COMMIT"] {
+            assert!(!writes_sql(sql), "{sql}");
+        }
+        for sql in [
+            "CREATE user", "DEFINE TABLE user", "SELECT * FROM (DELETE user)", "LET $x = (CREATE user)",
+            "IF true THEN (UPDATE user) END", "SELECT * FROM user WHERE id = (DELETE user)",
+        ] {
+            assert!(writes_sql(sql), "{sql}");
+        }
+    }
+}
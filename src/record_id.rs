@@ -0,0 +1,82 @@
+use std::{fmt, hash::{Hash, Hasher}, str::FromStr};
+use surrealdb::sql::{self, Id, Thing, Value};
+
+use crate::{Error, FromValue, IntoValue, Result};
+
+/// A pointer to a record, `table:id`. The id keeps SurrealDB's own representation so
+/// numeric, string and complex ids all round-trip unchanged.
+#[derive(Clone, Debug, Eq, PartialEq, PartialOrd)]
+pub struct RecordId {
+    pub table: String,
+    pub id: Id,
+}
+
+impl RecordId {
+    pub fn new<S: Into<String>, I: Into<Id>>(table: S, id: I) -> Self {
+        Self {
+            table: table.into(),
+            id: id.into(),
+        }
+    }
+}
+
+impl Hash for RecordId {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.table.hash(state);
+        self.id.to_raw().hash(state);
+    }
+}
+
+impl fmt::Display for RecordId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Thing::from(self.clone()).fmt(f)
+    }
+}
+
+impl FromStr for RecordId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(sql::thing(s)?.into())
+    }
+}
+
+impl From<Thing> for RecordId {
+    fn from(value: Thing) -> Self {
+        Self {
+            table: value.tb,
+            id: value.id,
+        }
+    }
+}
+
+impl From<RecordId> for Thing {
+    fn from(value: RecordId) -> Self {
+        Thing {
+            tb: value.table,
+            id: value.id,
+        }
+    }
+}
+
+impl From<RecordId> for Value {
+    fn from(value: RecordId) -> Self {
+        Value::Thing(value.into())
+    }
+}
+
+impl IntoValue for RecordId {
+    fn into_value(self) -> Value {
+        self.into()
+    }
+}
+
+impl FromValue for RecordId {
+    fn from_value(value: Value) -> Result<Self> {
+        match &value {
+            Value::Thing(t) => Ok(t.clone().into()),
+            Value::Strand(s) => s.as_str().parse().map_err(|_e| Error::cast_failed::<Self>(&value)),
+            _ => Err(Error::cast_failed::<Self>(&value)),
+        }
+    }
+}
@@ -0,0 +1,45 @@
+/// Replaces string and number literals in `sql` with `?`, leaving keywords, identifiers and
+/// `$params` intact, e.g. `SELECT * FROM user WHERE name = 'bob' LIMIT 5` becomes
+/// `SELECT * FROM user WHERE name = ? LIMIT ?`.
+pub fn redact_sql(sql: &str) -> String {
+    let mut out = String::with_capacity(sql.len());
+    let mut chars = sql.chars().peekable();
+    let mut prev = ' ';
+    while let Some(c) = chars.next() {
+        let in_word = prev.is_alphanumeric() || prev == '_' || prev == '$';
+        prev = match c {
+            '\'' | '"' => {
+                let mut escaped = false;
+                for next in chars.by_ref() {
+                    match next {
+                        _ if escaped => escaped = false,
+                        '\\' => escaped = true,
+                        _ if next == c => break,
+                        _ => {},
+                    }
+                }
+                '?'
+            },
+            '0'..='9' if !in_word => {
+                while chars.peek().is_some_and(|c| c.is_alphanumeric() || *c == '.' || *c == '_') {
+                    chars.next();
+                }
+                '?'
+            },
+            c => c,
+        };
+        out.push(prev);
+    }
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn redacts_literals() {
+        assert_eq!(redact_sql("SELECT * FROM user WHERE name = 'bob' LIMIT 5"), "SELECT * FROM user WHERE name = ? LIMIT ?");
+        assert_eq!(redact_sql(r#"UPDATE user2 SET a = "it\"s", b = 1.5e3, c = $p1"#), "UPDATE user2 SET a = ?, b = ?, c = $p1");
+    }
+}
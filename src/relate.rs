@@ -0,0 +1,86 @@
+use surrealdb::sql::{Thing, Value};
+
+use crate::{ident, ArgsBuilder, Db, IntoValue, Record, Result};
+
+/// A RELATE statement built by `Db::relate`. Record ids and edge data are bound as
+/// parameters and the edge table is checked with `ident`, so nothing else is interpolated
+/// into the SQL.
+pub struct RelateBuilder<'a> {
+    db: &'a Db,
+    from: Thing,
+    edge: String,
+    to: Thing,
+    data: Option<Value>,
+}
+
+impl <'a> RelateBuilder<'a> {
+    pub(crate) fn new(db: &'a Db, from: Thing, edge: &str, to: Thing) -> Self {
+        Self {
+            db,
+            from,
+            edge: edge.to_string(),
+            to,
+            data: None,
+        }
+    }
+
+    pub fn content(mut self, f: impl FnOnce(&mut ArgsBuilder)) -> Self {
+        let mut args = ArgsBuilder::default();
+        f(&mut args);
+        self.data = Some(args.into());
+        self
+    }
+
+    pub fn data<D: IntoValue>(mut self, data: D) -> Self {
+        self.data = Some(data.into_value());
+        self
+    }
+
+    /// Creates the edge and returns it. Fails with `Error::InvalidIdent` for an edge table
+    /// name `ident` rejects.
+    pub async fn execute(self) -> Result<Record> {
+        let edge = ident(&self.edge)?;
+        let sql = match self.data {
+            Some(_) => format!("RELATE ($from)->{edge}->($to) CONTENT $data"),
+            None => format!("RELATE ($from)->{edge}->($to)"),
+        };
+        self.db.query(sql)
+            .arg("from", self.from)
+            .arg("to", self.to)
+            .arg_opt("data", self.data)
+            .execute_one()
+            .await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeMap;
+
+    use super::*;
+    use crate::{QueryMiddleware, RecordId};
+
+    struct Tag;
+
+    impl QueryMiddleware for Tag {
+        fn before(&self, sql: &mut String, _vars: &mut BTreeMap<String, Value>) -> Result<()> {
+            sql.push_str(" -- tagged");
+            Ok(())
+        }
+    }
+
+    #[tokio::test]
+    async fn relate_survives_rewriting_middleware() {
+        let db = Db::memory("test", "relate").await.unwrap().middleware(Tag);
+        db.query("CREATE person:1; CREATE post:1").execute_check().await.unwrap();
+        let edge = db.relate(RecordId::new("person", 1), "wrote", RecordId::new("post", 1))
+            .content(|args| { args.arg("at", 1); })
+            .execute()
+            .await
+            .unwrap();
+        assert_eq!(edge.get_as::<RecordId>("id").unwrap().table, "wrote");
+        let posts = db.query("SELECT ->wrote->post AS posts FROM person:1").execute_scalar::<Vec<RecordId>>().await.unwrap();
+        assert_eq!(posts, [RecordId::new("post", 1)]);
+        assert!(db.query("SELECT * FROM edge").execute().await.unwrap().is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+use std::future::Future;
+
+use serde::Deserialize;
+use surrealdb::sql::{Id, Value};
+
+use crate::{from_json, ident, ArgsBuilder, DbClient, Error, Record, Result, ValueKind};
+
+/// A `DbClient` that sends queries to a SurrealDB server through its HTTP `/sql` endpoint, so
+/// code written against `DbClient` runs unchanged against an embedded `Db` in tests and a
+/// server in production.
+///
+/// Arguments are sent as LET statements ahead of the query, with strings JSON-escaped and
+/// record ids, datetimes, durations and UUIDs cast back to their types, so they can't change
+/// the query. Argument names must be plain identifiers. Results come back as JSON, in which
+/// record ids are strings; the `id` field of each record is turned back into a record id,
+/// and `FromValue for RecordId` accepts the others as strings.
+///
+/// Only the `DbClient` surface is available, not that of `Db` and `QueryBuilder`. The
+/// `strict` argument is ignored, as strict mode is a setting of the server.
+#[derive(Clone)]
+pub struct RemoteDb {
+    http: reqwest::Client,
+    url: String,
+    ns: String,
+    db: String,
+    auth: Option<(String, String)>,
+}
+
+#[derive(Deserialize)]
+struct Response {
+    status: String,
+    #[serde(default)]
+    result: serde_json::Value,
+    #[serde(default)]
+    detail: String,
+}
+
+impl RemoteDb {
+    /// A client for the server at `url`, such as `http://localhost:8000`, running queries in
+    /// namespace `ns` and database `db`. No connection is made until the first query.
+    pub fn new(url: &str, ns: &str, db: &str) -> Self {
+        Self {
+            http: reqwest::Client::new(),
+            url: format!("{}/sql", url.trim_end_matches('/')),
+            ns: ns.to_string(),
+            db: db.to_string(),
+            auth: None,
+        }
+    }
+
+    /// Signs every request in as `user` with HTTP basic authentication.
+    pub fn auth(mut self, user: &str, pass: &str) -> Self {
+        self.auth = Some((user.to_string(), pass.to_string()));
+        self
+    }
+
+    async fn send(&self, sql: &str, args: &ArgsBuilder) -> Result<Vec<Result<Vec<Record>>>> {
+        let mut body = String::new();
+        for (k, v) in &args.0 {
+            body.push_str(&format!("LET ${} = {};\n", ident(k)?, literal(v)?));
+        }
+        body.push_str(sql);
+        let mut req = self.http.post(&self.url)
+            .header("Accept", "application/json")
+            .header("NS", &self.ns)
+            .header("DB", &self.db)
+            .body(body);
+        if let Some((user, pass)) = &self.auth {
+            req = req.basic_auth(user, Some(pass));
+        }
+        let res = req.send().await?;
+        if !res.status().is_success() {
+            return Err(Error::Remote(res.text().await?));
+        }
+        let responses: Vec<Response> = res.json().await?;
+        responses.into_iter()
+            .skip(args.0.len())
+            .map(|r| match r.status.as_str() {
+                "OK" => Ok(Ok(records(from_json(r.result)))),
+                _ => Ok(Err(Error::Remote(r.detail))),
+            })
+            .collect()
+    }
+}
+
+/// `value` as a SurrealQL literal that parses back to the same value.
+fn literal(value: &Value) -> Result<String> {
+    let quote = |s: &str| serde_json::to_string(s).map_err(Error::from);
+    Ok(match value {
+        Value::None|Value::Null|Value::True|Value::False|Value::Number(_) => value.to_string(),
+        Value::Strand(s) => quote(s.as_str())?,
+        Value::Datetime(dt) => format!("<datetime> {}", quote(&dt.0.to_rfc3339())?),
+        Value::Duration(d) => format!("<duration> {}", quote(&d.to_string())?),
+        Value::Uuid(u) => format!("<uuid> {}", quote(&u.0.to_string())?),
+        Value::Array(arr) => {
+            let items = arr.iter().map(literal).collect::<Result<Vec<_>>>()?;
+            format!("[{}]", items.join(", "))
+        },
+        Value::Object(obj) => {
+            let fields = obj.iter().map(|(k, v)| Ok(format!("{}: {}", quote(k)?, literal(v)?))).collect::<Result<Vec<_>>>()?;
+            format!("{{ {} }}", fields.join(", "))
+        },
+        Value::Thing(t) => {
+            let id = match &t.id {
+                Id::Number(n) => n.to_string(),
+                Id::String(s) => quote(s)?,
+                Id::Array(arr) => literal(&Value::Array(arr.clone()))?,
+                Id::Object(obj) => literal(&Value::Object(obj.clone()))?,
+            };
+            format!("type::thing({}, {id})", quote(&t.tb)?)
+        },
+        Value::Geometry(g) => serde_json::to_string(g)?,
+        v => return Err(Error::Remote(format!("{} values can't be sent as parameters", ValueKind::of(v)))),
+    })
+}
+
+fn records(value: Value) -> Vec<Record> {
+    let mut records = Record::from_result(value);
+    for record in &mut records {
+        if let Some(Value::Strand(id)) = record.0.get("id") {
+            if let Ok(thing) = surrealdb::sql::thing(id.as_str()) {
+                record.0.insert("id".to_string(), Value::Thing(thing));
+            }
+        }
+    }
+    records
+}
+
+impl DbClient for RemoteDb {
+    fn execute_all(&self, sql: &str, args: ArgsBuilder, _strict: bool) -> impl Future<Output = Result<Vec<Result<Vec<Record>>>>> + Send {
+        let sql = sql.to_string();
+        async move { self.send(&sql, &args).await }
+    }
+
+    fn transaction(&self, statements: &[&str], args: ArgsBuilder, _strict: bool) -> impl Future<Output = Result<Vec<Vec<Record>>>> + Send {
+        let mut sql = String::from("BEGIN TRANSACTION;\n");
+        for stmt in statements {
+            sql.push_str(stmt.trim().trim_end_matches(';'));
+            sql.push_str(";\n");
+        }
+        sql.push_str("COMMIT TRANSACTION;");
+        async move {
+            // Statements other than the one that failed report that they weren't executed;
+            // if all of them do, the commit itself failed.
+            let not_executed = surrealdb::Error::QueryNotExecuted.to_string();
+            let mut records = Vec::new();
+            let mut not_run = None;
+            for res in self.send(&sql, &args).await? {
+                match res {
+                    Ok(r) => records.push(r),
+                    Err(Error::Remote(e)) if e == not_executed => {
+                        not_run.get_or_insert(Error::Remote(e));
+                    },
+                    Err(e) => return Err(e),
+                }
+            }
+            match not_run {
+                Some(e) => Err(e),
+                None => Ok(records),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use surrealdb::sql::{Statement, Value};
+
+    use crate::{ArgsBuilder, RecordId};
+
+    use super::literal;
+
+    fn parse_back(value: &Value) -> Value {
+        let query = surrealdb::sql::parse(&format!("RETURN {}", literal(value).unwrap())).unwrap();
+        assert_eq!(query.0.0.len(), 1);
+        match query.0.0.into_iter().next() {
+            Some(Statement::Output(s)) => s.what,
+            s => panic!("unexpected statement {s:?}"),
+        }
+    }
+
+    #[test]
+    fn strings_cannot_escape_the_literal() {
+        for s in [r#"x"; DELETE user; --"#, r#"z" }; CREATE pwned:1; "#, "a\\\"b", "line\nbreak", "'single'"] {
+            assert_eq!(parse_back(&Value::from(s)), Value::from(s));
+        }
+    }
+
+    #[test]
+    fn nested_values_keep_their_shape() {
+        let mut args = ArgsBuilder::default();
+        args.arg("name", r#"say "hi""#).arg("n", 3).arg("tags", vec!["a", "b"]);
+        let value = Value::from(args);
+        let Value::Object(parsed) = parse_back(&value) else { panic!("not an object") };
+        assert_eq!(Value::Object(parsed), value);
+    }
+
+    #[test]
+    fn record_ids_become_thing_casts() {
+        let id = RecordId::new("user", r#"a"b"#);
+        assert_eq!(literal(&Value::Thing(id.into())).unwrap(), r#"type::thing("user", "a\"b")"#);
+    }
+}
@@ -0,0 +1,81 @@
+use std::{collections::hash_map::RandomState, hash::{BuildHasher, Hasher}, time::Duration};
+
+/// How many times a transaction that failed with `Error::Conflict` is attempted, and how long
+/// to wait between attempts. The delay doubles after each attempt, starting at `base_delay`
+/// and capped at `max_delay`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct RetryPolicy {
+    max_attempts: u32,
+    base_delay: Duration,
+    max_delay: Duration,
+    jitter: bool,
+}
+
+impl RetryPolicy {
+    /// A single attempt, i.e. conflicts are returned straight away.
+    pub const NONE: Self = Self {
+        max_attempts: 1,
+        base_delay: Duration::ZERO,
+        max_delay: Duration::ZERO,
+        jitter: false,
+    };
+
+    pub fn new(max_attempts: u32) -> Self {
+        Self {
+            max_attempts: max_attempts.max(1),
+            base_delay: Duration::from_millis(10),
+            max_delay: Duration::from_secs(1),
+            jitter: true,
+        }
+    }
+
+    pub fn backoff(mut self, base_delay: Duration, max_delay: Duration) -> Self {
+        self.base_delay = base_delay;
+        self.max_delay = max_delay.max(base_delay);
+        self
+    }
+
+    /// With jitter enabled each delay is picked at random between zero and the backoff value,
+    /// so competing writers don't retry in lockstep.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// The delay before attempt `attempt + 1`, where `attempt` counts from 1.
+    pub(crate) fn delay(&self, attempt: u32) -> Duration {
+        let factor = 1u32.checked_shl(attempt.saturating_sub(1)).unwrap_or(u32::MAX);
+        let delay = self.base_delay.saturating_mul(factor).min(self.max_delay);
+        if !self.jitter || delay.is_zero() {
+            return delay;
+        }
+        // RandomState is seeded per instance, which is plenty for spreading retries out.
+        let random = RandomState::new().build_hasher().finish();
+        delay.mul_f64((random >> 11) as f64 / (1u64 << 53) as f64)
+    }
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self::NONE
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn delay_backs_off_exponentially() {
+        let policy = RetryPolicy::new(5).backoff(Duration::from_millis(10), Duration::from_millis(50)).jitter(false);
+        let delays: Vec<_> = [1, 2, 3, 4, 100].into_iter().map(|n| policy.delay(n).as_millis()).collect();
+        assert_eq!(delays, [10, 20, 40, 50, 50]);
+        let policy = policy.jitter(true);
+        assert!((1..10).all(|n| policy.delay(n) <= Duration::from_millis(50)));
+        assert_eq!(RetryPolicy::NONE.delay(1), Duration::ZERO);
+    }
+}
@@ -0,0 +1,250 @@
+//! Builders for DEFINE TABLE / FIELD / INDEX statements, run with `Db::define`.
+
+use std::fmt;
+
+use crate::{ident, Db, Error, Result};
+
+pub fn table(name: &str) -> TableDef {
+    TableDef {
+        name: name.to_string(),
+        schemafull: None,
+        fields: Vec::new(),
+        indexes: Vec::new(),
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum FieldType {
+    Any,
+    Array,
+    Bool,
+    Datetime,
+    Decimal,
+    Duration,
+    Float,
+    Int,
+    Number,
+    Object,
+    Strand,
+    Geometry,
+    /// A record link to one of the given tables.
+    Record(Vec<String>),
+}
+
+impl FieldType {
+    pub fn record(table: &str) -> Self {
+        FieldType::Record(vec![table.to_string()])
+    }
+}
+
+impl fmt::Display for FieldType {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            FieldType::Any => "any",
+            FieldType::Array => "array",
+            FieldType::Bool => "bool",
+            FieldType::Datetime => "datetime",
+            FieldType::Decimal => "decimal",
+            FieldType::Duration => "duration",
+            FieldType::Float => "float",
+            FieldType::Int => "int",
+            FieldType::Number => "number",
+            FieldType::Object => "object",
+            FieldType::Strand => "string",
+            FieldType::Geometry => "geometry(feature)",
+            FieldType::Record(tables) => {
+                let tables: Vec<_> = tables.iter().map(|t| checked(t)).collect();
+                return write!(f, "record({})", tables.join(", "));
+            },
+        };
+        f.write_str(name)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct FieldDef {
+    name: String,
+    kind: Option<FieldType>,
+    value: Option<String>,
+    assert: Option<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct IndexDef {
+    name: String,
+    columns: Vec<String>,
+    unique: bool,
+}
+
+/// A table together with its fields and indexes. `value` and `assert` apply to the field
+/// added last. Names must be accepted by `ident`; field names and index columns are checked
+/// as paths, one part per `.`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TableDef {
+    name: String,
+    schemafull: Option<bool>,
+    fields: Vec<FieldDef>,
+    indexes: Vec<IndexDef>,
+}
+
+impl TableDef {
+    pub fn schemafull(mut self) -> Self {
+        self.schemafull = Some(true);
+        self
+    }
+
+    pub fn schemaless(mut self) -> Self {
+        self.schemafull = Some(false);
+        self
+    }
+
+    pub fn field(mut self, name: &str, kind: FieldType) -> Self {
+        self.fields.push(FieldDef {
+            name: name.to_string(),
+            kind: Some(kind),
+            value: None,
+            assert: None,
+        });
+        self
+    }
+
+    /// A field with no TYPE clause, for nested paths such as `tags.*` or computed values.
+    pub fn untyped_field(mut self, name: &str) -> Self {
+        self.fields.push(FieldDef {
+            name: name.to_string(),
+            kind: None,
+            value: None,
+            assert: None,
+        });
+        self
+    }
+
+    pub fn value(mut self, expr: &str) -> Self {
+        if let Some(field) = self.fields.last_mut() {
+            field.value = Some(expr.to_string());
+        }
+        self
+    }
+
+    pub fn assert(mut self, expr: &str) -> Self {
+        if let Some(field) = self.fields.last_mut() {
+            field.assert = Some(expr.to_string());
+        }
+        self
+    }
+
+    pub fn index(mut self, name: &str, columns: &[&str], unique: bool) -> Self {
+        self.indexes.push(IndexDef {
+            name: name.to_string(),
+            columns: columns.iter().map(|c| c.to_string()).collect(),
+            unique,
+        });
+        self
+    }
+
+    /// The DEFINE statements, one per line. Fails with `Error::InvalidIdent` for a name
+    /// `ident` rejects.
+    pub fn to_sql(&self) -> Result<String> {
+        let fields = self.fields.iter().flat_map(|f| {
+            let tables = match &f.kind {
+                Some(FieldType::Record(tables)) => tables.as_slice(),
+                _ => &[],
+            };
+            parts(&f.name).chain(tables.iter().map(String::as_str))
+        });
+        let indexes = self.indexes.iter().flat_map(|i| std::iter::once(i.name.as_str()).chain(i.columns.iter().flat_map(|c| parts(c))));
+        match std::iter::once(self.name.as_str()).chain(fields).chain(indexes).find(|name| ident(name).is_err()) {
+            Some(name) => Err(Error::InvalidIdent(name.to_string())),
+            None => Ok(self.to_string()),
+        }
+    }
+}
+
+/// The parts of a field path such as `address.city` or `tags.*`, other than `*`.
+fn parts(path: &str) -> impl Iterator<Item = &str> {
+    path.split('.').filter(|part| *part != "*")
+}
+
+/// `name` if `ident` accepts it. A name it rejects is written as a string, which fails to
+/// parse rather than changing the statement; `to_sql` reports it instead.
+fn checked(name: &str) -> String {
+    ident(name).unwrap_or_else(|_e| format!("{name:?}"))
+}
+
+fn checked_path(path: &str) -> String {
+    let parts: Vec<_> = path.split('.').map(|part| match part {
+        "*" => part.to_string(),
+        _ => checked(part),
+    }).collect();
+    parts.join(".")
+}
+
+impl fmt::Display for TableDef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let table = checked(&self.name);
+        write!(f, "DEFINE TABLE {table}")?;
+        match self.schemafull {
+            Some(true) => write!(f, " SCHEMAFULL")?,
+            Some(false) => write!(f, " SCHEMALESS")?,
+            None => {},
+        }
+        writeln!(f, ";")?;
+        for field in &self.fields {
+            write!(f, "DEFINE FIELD {} ON TABLE {table}", checked_path(&field.name))?;
+            if let Some(kind) = &field.kind {
+                write!(f, " TYPE {kind}")?;
+            }
+            if let Some(value) = &field.value {
+                write!(f, " VALUE {value}")?;
+            }
+            if let Some(assert) = &field.assert {
+                write!(f, " ASSERT {assert}")?;
+            }
+            writeln!(f, ";")?;
+        }
+        for index in &self.indexes {
+            let columns: Vec<_> = index.columns.iter().map(|c| checked_path(c)).collect();
+            write!(f, "DEFINE INDEX {} ON TABLE {table} FIELDS {}", checked(&index.name), columns.join(", "))?;
+            if index.unique {
+                write!(f, " UNIQUE")?;
+            }
+            writeln!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+impl Db {
+    /// Runs the DEFINE statements for `table`. Redefining an existing table, field or index
+    /// replaces it.
+    pub async fn define(&self, table: &TableDef) -> Result<()> {
+        self.query(table.to_sql()?).execute_check().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn define_checks_names() {
+        let db = Db::memory("test", "schema").await.unwrap();
+        let def = table("person")
+            .schemafull()
+            .field("full_name", FieldType::Strand)
+            .field("owner", FieldType::record("user_account"))
+            .untyped_field("tags.*")
+            .index("by_name", &["full_name"], true);
+        assert_eq!(def.to_sql().unwrap(), "\
+DEFINE TABLE person SCHEMAFULL;
+DEFINE FIELD full_name ON TABLE person TYPE string;
+DEFINE FIELD owner ON TABLE person TYPE record(user_account);
+DEFINE FIELD tags.* ON TABLE person;
+DEFINE INDEX by_name ON TABLE person FIELDS full_name UNIQUE;
+");
+        db.define(&def).await.unwrap();
+        for bad in [table("my table"), table("user").field("a`b", FieldType::Int), table("user").index("i", &["x.y z"], false)] {
+            assert!(matches!(db.define(&bad).await, Err(Error::InvalidIdent(_))));
+        }
+    }
+}
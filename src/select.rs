@@ -0,0 +1,173 @@
+use std::fmt;
+
+use crate::{ident::ident_path, ArgsBuilder, Db, IntoValue, QueryBuilder, Record, Result};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Order {
+    Asc,
+    Desc,
+}
+
+impl fmt::Display for Order {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Order::Asc => "ASC",
+            Order::Desc => "DESC",
+        })
+    }
+}
+
+/// A SELECT assembled piece by piece, started with `Db::select_from`. The table is bound as
+/// `$table`, so that name is reserved; filters are SurrealQL conditions combined with AND,
+/// whose values should be bound with `arg`. Fields, order and fetch paths are checked like
+/// `ident` does, part by part, and an invalid one fails the query with `Error::InvalidIdent`;
+/// only filters are raw SurrealQL.
+pub struct SelectBuilder<'a> {
+    db: &'a Db,
+    table: String,
+    fields: Vec<String>,
+    filters: Vec<String>,
+    order: Vec<(String, Order)>,
+    limit: Option<usize>,
+    start: Option<usize>,
+    fetch: Vec<String>,
+    args: ArgsBuilder,
+    invalid_ident: Option<String>,
+}
+
+impl <'a> SelectBuilder<'a> {
+    pub(crate) fn new(db: &'a Db, table: &str) -> Self {
+        Self {
+            db,
+            table: table.to_string(),
+            fields: Vec::new(),
+            filters: Vec::new(),
+            order: Vec::new(),
+            limit: None,
+            start: None,
+            fetch: Vec::new(),
+            args: Default::default(),
+            invalid_ident: None,
+        }
+    }
+
+    /// `path` once `ident_path` accepts it; the first one rejected is kept to fail the query.
+    fn checked(&mut self, path: &str) -> String {
+        if ident_path(path).is_err() {
+            self.invalid_ident.get_or_insert_with(|| path.to_string());
+        }
+        path.to_string()
+    }
+
+    /// Selects only `fields` instead of `*`.
+    pub fn fields(mut self, fields: &[&str]) -> Self {
+        self.fields = fields.iter().map(|f| self.checked(f)).collect();
+        self
+    }
+
+    pub fn filter(mut self, cond: &str) -> Self {
+        self.filters.push(cond.to_string());
+        self
+    }
+
+    /// Adds `cond` only when `enabled`, for filters that depend on optional input.
+    pub fn filter_if(self, enabled: bool, cond: &str) -> Self {
+        match enabled {
+            true => self.filter(cond),
+            false => self,
+        }
+    }
+
+    pub fn arg<S: Into<String>, V: IntoValue>(mut self, key: S, value: V) -> Self {
+        self.args.arg(key, value);
+        self
+    }
+
+    pub fn order_by(mut self, field: &str, order: Order) -> Self {
+        let field = self.checked(field);
+        self.order.push((field, order));
+        self
+    }
+
+    pub fn limit(mut self, limit: usize) -> Self {
+        self.limit = Some(limit);
+        self
+    }
+
+    pub fn start(mut self, start: usize) -> Self {
+        self.start = Some(start);
+        self
+    }
+
+    pub fn fetch(mut self, field: &str) -> Self {
+        let field = self.checked(field);
+        self.fetch.push(field);
+        self
+    }
+
+    pub fn to_sql(&self) -> String {
+        let mut sql = String::from("SELECT ");
+        match self.fields.is_empty() {
+            true => sql.push('*'),
+            false => sql.push_str(&self.fields.join(", ")),
+        }
+        sql.push_str(" FROM type::table($table)");
+        if !self.filters.is_empty() {
+            let filters: Vec<_> = self.filters.iter().map(|f| format!("({f})")).collect();
+            sql.push_str(" WHERE ");
+            sql.push_str(&filters.join(" AND "));
+        }
+        if !self.order.is_empty() {
+            let order: Vec<_> = self.order.iter().map(|(field, order)| format!("{field} {order}")).collect();
+            sql.push_str(" ORDER BY ");
+            sql.push_str(&order.join(", "));
+        }
+        if let Some(limit) = self.limit {
+            sql.push_str(&format!(" LIMIT {limit}"));
+        }
+        if let Some(start) = self.start {
+            sql.push_str(&format!(" START {start}"));
+        }
+        if !self.fetch.is_empty() {
+            sql.push_str(" FETCH ");
+            sql.push_str(&self.fetch.join(", "));
+        }
+        sql
+    }
+
+    /// The equivalent `QueryBuilder`, for its other ways of executing such as `execute_one`,
+    /// `paginate` or `execute_stream`.
+    pub fn into_query(self) -> QueryBuilder<'a> {
+        let mut query = self.db.query(self.to_sql());
+        query.args = self.args;
+        if let Some(path) = self.invalid_ident {
+            query.invalid_ident.get_or_insert(path);
+        }
+        query.arg_table("table", &self.table)
+    }
+
+    pub async fn execute(self) -> Result<Vec<Record>> {
+        self.into_query().execute().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Error;
+
+    #[tokio::test]
+    async fn rejects_raw_sql_outside_filters() {
+        let db = Db::memory("test", "select").await.unwrap();
+        db.query("CREATE user SET name = 'a', address = { city: 'x' }").execute().await.unwrap();
+        let query = db.select_from("user").fields(&["name", "address.city"]).order_by("name", Order::Asc).fetch("address");
+        assert_eq!(query.execute().await.unwrap().len(), 1);
+        let query = db.select_from("user").fields(&["name, password"]);
+        assert!(matches!(query.execute().await, Err(Error::InvalidIdent(f)) if f == "name, password"));
+        let query = db.select_from("user").order_by("name; DELETE user", Order::Asc);
+        assert!(matches!(query.execute().await, Err(Error::InvalidIdent(_))));
+        let query = db.select_from("user").fetch("address FETCH x");
+        assert!(matches!(query.execute().await, Err(Error::InvalidIdent(_))));
+        assert_eq!(db.count("user", None).await.unwrap(), 1);
+    }
+}
@@ -0,0 +1,114 @@
+use std::marker::PhantomData;
+
+use surrealdb::sql::{Id, Thing, Value};
+
+use crate::{Db, FromRecord, IntoValue, Result, SelectBuilder};
+
+/// Typed access to the records of one table, returned by `Db::table`. Records are read with
+/// `FromRecord` and written with `IntoValue`; the CRUD helpers on `Db` do the work.
+pub struct Table<'a, T> {
+    db: &'a Db,
+    name: String,
+    soft_delete: bool,
+    with_deleted: bool,
+    kind: PhantomData<fn() -> T>,
+}
+
+impl <'a, T> Table<'a, T> {
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Makes `delete` mark records with `Db::soft_delete` instead of removing them, and
+    /// hides marked records from `get` and `list`.
+    pub fn soft_delete(mut self) -> Self {
+        self.soft_delete = true;
+        self
+    }
+
+    /// The same table with soft-deleted records visible to `get` and `list`.
+    pub fn with_deleted(&self) -> Self {
+        Self {
+            db: self.db,
+            name: self.name.clone(),
+            soft_delete: self.soft_delete,
+            with_deleted: true,
+            kind: PhantomData,
+        }
+    }
+
+    fn hides_deleted(&self) -> bool {
+        self.soft_delete && !self.with_deleted
+    }
+}
+
+impl <'a, T: FromRecord + IntoValue + Clone> Table<'a, T> {
+    pub(crate) fn new(db: &'a Db, name: &str) -> Self {
+        Self {
+            db,
+            name: name.to_string(),
+            soft_delete: false,
+            with_deleted: false,
+            kind: PhantomData,
+        }
+    }
+
+    fn thing(&self, id: impl Into<Id>) -> Thing {
+        Thing {
+            tb: self.name.clone(),
+            id: id.into(),
+        }
+    }
+
+    pub async fn get(&self, id: impl Into<Id>) -> Result<Option<T>> {
+        if !self.hides_deleted() {
+            return self.db.select(self.thing(id)).await?.map(T::from_record).transpose();
+        }
+        self.db.query("SELECT * FROM $thing WHERE deleted_at IS NONE")
+            .arg("thing", self.thing(id))
+            .execute_first()
+            .await?
+            .map(T::from_record)
+            .transpose()
+    }
+
+    /// The records selected by the query `select` builds from a SELECT of the whole table,
+    /// e.g. `table.list(|q| q.filter("age >= $min").arg("min", 18)).await`.
+    pub async fn list(&self, select: impl FnOnce(SelectBuilder<'a>) -> SelectBuilder<'a>) -> Result<Vec<T>> {
+        let query = self.db.select_from(&self.name).filter_if(self.hides_deleted(), "deleted_at IS NONE");
+        select(query)
+            .execute()
+            .await?
+            .into_iter()
+            .map(T::from_record)
+            .collect()
+    }
+
+    /// Creates a record from `item` and returns it as stored. An `id` field in `item`, either
+    /// a record id or the id part alone, fixes the record id; creating a record that already
+    /// exists fails.
+    pub async fn insert(&self, item: &T) -> Result<T> {
+        let data = item.clone().into_value();
+        let query = match data.pick(&["id".into()]) {
+            Value::None|Value::Null => self.db.query("CREATE type::table($table) CONTENT $data").arg_table("table", &self.name),
+            Value::Thing(thing) => self.db.query("CREATE $thing CONTENT $data").arg("thing", thing),
+            id => self.db.query("CREATE type::thing($table, $id) CONTENT $data").arg_table("table", &self.name).arg("id", id),
+        };
+        T::from_record(query.arg("data", data).execute_one().await?)
+    }
+
+    /// Replaces the content of record `id`, creating it if it does not exist.
+    pub async fn update(&self, id: impl Into<Id>, item: &T) -> Result<T> {
+        T::from_record(self.db.update(self.thing(id), item.clone()).await?)
+    }
+
+    /// Deletes record `id` and returns it, or `None` if it did not exist. With `soft_delete`
+    /// the record is marked instead and returned as marked, or `None` if it already was.
+    pub async fn delete(&self, id: impl Into<Id>) -> Result<Option<T>> {
+        let record = match self.soft_delete {
+            true => self.db.soft_delete(self.thing(id)).await?,
+            false => self.db.delete(self.thing(id)).await?,
+        };
+        record.map(T::from_record).transpose()
+    }
+}
@@ -0,0 +1,19 @@
+use uuid::Uuid;
+
+use crate::{Db, Migration, Result};
+
+impl Db {
+    /// An in-memory database with a namespace and database name unique to this call, so
+    /// parallel tests never share data.
+    pub async fn test() -> Result<Self> {
+        let id = Uuid::new_v4().simple();
+        Self::connect("memory", &format!("test_{id}"), &format!("test_{id}")).await
+    }
+
+    /// Like `test`, with `migrations` already applied.
+    pub async fn test_with_migrations(migrations: &[Migration]) -> Result<Self> {
+        let db = Self::test().await?;
+        db.migrate(migrations).await?;
+        Ok(db)
+    }
+}
@@ -0,0 +1,100 @@
+use std::{collections::BTreeMap, fmt::Display, future::Future, sync::Arc, time::{Duration, Instant}};
+
+use surrealdb::{Response, sql::Value};
+
+use crate::{Db, QueryObserver, QueryStats, Result};
+
+#[cfg(feature = "tracing")]
+use tracing::{Instrument, Span, field};
+
+/// Wraps a single round trip to the datastore, reporting it to the `Db`'s observer and, with
+/// the `tracing` feature, to a span. With neither this only converts the error.
+pub(crate) struct QueryTrace {
+    observer: Option<(Arc<dyn QueryObserver>, String)>,
+    start: Instant,
+    #[cfg(feature = "tracing")]
+    span: Span,
+    #[cfg(feature = "tracing")]
+    slow: Option<Duration>,
+}
+
+impl QueryTrace {
+    #[cfg_attr(not(feature = "tracing"), allow(unused_variables))]
+    pub(crate) fn new(db: &Db, sql: &dyn Display, vars: Option<&BTreeMap<String, Value>>) -> Self {
+        let observer = db.observer.clone().map(|observer| {
+            let sql = sql.to_string();
+            observer.on_start(&sql);
+            (observer, sql)
+        });
+        Self {
+            observer,
+            start: Instant::now(),
+            #[cfg(feature = "tracing")]
+            span: {
+                let params = vars.map(|v| v.keys().map(String::as_str).collect::<Vec<_>>().join(", ")).unwrap_or_default();
+                tracing::info_span!("query", sql = %sql, params, rows = field::Empty, elapsed_ms = field::Empty)
+            },
+            #[cfg(feature = "tracing")]
+            slow: db.slow_query,
+        }
+    }
+
+    pub(crate) async fn run(self, fut: impl Future<Output = std::result::Result<Vec<Response>, surrealdb::Error>>) -> Result<Vec<Response>> {
+        #[cfg(feature = "tracing")]
+        let fut = fut.instrument(self.span.clone());
+        let res = fut.await.map_err(Into::into);
+        self.finish(&res);
+        res
+    }
+
+    fn finish(&self, res: &Result<Vec<Response>>) {
+        #[cfg(not(feature = "tracing"))]
+        if self.observer.is_none() {
+            return;
+        }
+        let elapsed = self.start.elapsed();
+        let stats = res.as_ref().ok().map(|responses| stats(elapsed, responses));
+        if let Some((observer, sql)) = &self.observer {
+            match (res, &stats) {
+                (Err(e), _) => observer.on_error(sql, elapsed, e),
+                (Ok(_), Some(stats)) => observer.on_finish(sql, stats),
+                (Ok(_), None) => {},
+            }
+        }
+        #[cfg(feature = "tracing")]
+        self.finish_span(elapsed, res, stats);
+    }
+
+    #[cfg(feature = "tracing")]
+    fn finish_span(&self, elapsed: Duration, res: &Result<Vec<Response>>, stats: Option<QueryStats>) {
+        self.span.record("elapsed_ms", elapsed.as_secs_f64() * 1000.0);
+        match res {
+            Ok(responses) => {
+                self.span.record("rows", stats.map_or(0, |s| s.rows));
+                for r in responses {
+                    if let Err(e) = &r.result {
+                        tracing::debug!(parent: &self.span, error = %e, "statement failed");
+                    }
+                }
+            },
+            Err(e) => tracing::error!(parent: &self.span, error = %e, "query failed"),
+        }
+        if self.slow.is_some_and(|slow| elapsed >= slow) {
+            tracing::warn!(parent: &self.span, elapsed_ms = elapsed.as_secs_f64() * 1000.0, "slow query");
+        }
+    }
+}
+
+fn stats(elapsed: Duration, responses: &[Response]) -> QueryStats {
+    let rows = responses.iter().map(|r| match &r.result {
+        Ok(Value::Array(arr)) => arr.len(),
+        Ok(Value::None) | Err(_) => 0,
+        Ok(_) => 1,
+    }).sum();
+    QueryStats {
+        elapsed,
+        rows,
+        statements: responses.len(),
+        failed: responses.iter().filter(|r| r.result.is_err()).count(),
+    }
+}
@@ -0,0 +1,188 @@
+use std::{mem, sync::{Arc, Mutex, MutexGuard, PoisonError}};
+
+use crate::{params, ArgsBuilder, ArrayBuilder, Db, Error, IntoValue, Record, Result};
+
+#[derive(Default)]
+struct Queue {
+    statements: Vec<String>,
+    args: ArgsBuilder,
+}
+
+/// Statements queued with `query` are sent together, wrapped in BEGIN/COMMIT, when the
+/// transaction is committed. Arguments share a single scope across all statements.
+///
+/// Clones share the same queue, so a handle can be moved into an async block while another
+/// is kept for committing.
+#[derive(Clone)]
+pub struct Transaction<'a> {
+    db: &'a Db,
+    queue: Arc<Mutex<Queue>>,
+    strict: bool,
+}
+
+impl <'a> Transaction<'a> {
+    pub fn new(db: &'a Db) -> Self {
+        Self {
+            db,
+            queue: Default::default(),
+            strict: db.strict,
+        }
+    }
+
+    /// Commits in strict mode or not, instead of following the `Db`.
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.strict = strict;
+        self
+    }
+
+    fn queue(&self) -> MutexGuard<'_, Queue> {
+        self.queue.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn query(&self, sql: &str) -> TransactionQuery<'_, 'a> {
+        self.queue().statements.push(sql.trim().trim_end_matches(';').to_string());
+        TransactionQuery { tx: self }
+    }
+
+    /// Returns the records produced by each queued statement, in order. If any statement
+    /// fails the whole transaction is rolled back and the first failure is returned.
+    /// Conflicts are retried according to the `Db`'s `RetryPolicy`.
+    pub async fn commit(self) -> Result<Vec<Vec<Record>>> {
+        let Queue { statements, args } = mem::take(&mut *self.queue());
+        if statements.is_empty() {
+            return Ok(Default::default());
+        }
+        let mut sql = String::from("BEGIN TRANSACTION;\n");
+        for stmt in &statements {
+            sql.push_str(stmt);
+            sql.push_str(";\n");
+        }
+        sql.push_str("COMMIT TRANSACTION;");
+        if self.db.check_params {
+            params::check(&sql, &args.0)?;
+        }
+        let policy = self.db.retry;
+        let mut attempt = 1;
+        loop {
+            match self.try_commit(&sql, &statements, &args).await {
+                Err(e) if matches!(e.root(), Error::Conflict(_)) && attempt < policy.max_attempts() => {
+                    tokio::time::sleep(policy.delay(attempt)).await;
+                    attempt += 1;
+                },
+                res => return res,
+            }
+        }
+    }
+
+    async fn try_commit(&self, sql: &str, statements: &[String], args: &ArgsBuilder) -> Result<Vec<Vec<Record>>> {
+        let res = self.db.execute_sql(sql, Some(args.0.clone()), self.strict).await?;
+        let matches_queue = res.len() == statements.len();
+        let mut results = Vec::with_capacity(res.len());
+        let mut failure = None;
+        for (i, r) in res.into_iter().enumerate() {
+            match r.result {
+                Ok(v) => results.push(Record::from_result(v)),
+                Err(surrealdb::Error::QueryNotExecuted) => {},
+                Err(e) => {
+                    failure.get_or_insert((i, e));
+                },
+            }
+        }
+        match failure {
+            // A queued query may hold several statements, in which case responses can't be
+            // matched to queue entries and the whole transaction is reported instead.
+            Some((i, e)) if matches_queue => Err(Error::from(e).in_statement(&statements[i], i)),
+            Some((i, e)) => Err(Error::from(e).in_statement(sql, i)),
+            // Every statement succeeded but was then marked as not executed, which only
+            // happens when the commit itself fails. The datastore drops the cause; on the
+            // optimistic engines it is a conflict with a concurrent writer.
+            None if results.is_empty() => Err(Error::Conflict(surrealdb::Error::QueryNotExecuted)),
+            None => Ok(results),
+        }
+    }
+}
+
+pub struct TransactionQuery<'t, 'a> {
+    tx: &'t Transaction<'a>,
+}
+
+impl <'t, 'a> TransactionQuery<'t, 'a> {
+    pub fn arg<S: Into<String>, V: IntoValue>(self, key: S, value: V) -> Self {
+        self.tx.queue().args.arg(key, value);
+        self
+    }
+
+    pub fn sub_args<S: Into<String>>(self, key: S, f: impl FnOnce(&mut ArgsBuilder)) -> Self {
+        let mut sub_args = ArgsBuilder::default();
+        f(&mut sub_args);
+        self.tx.queue().args.arg(key, sub_args);
+        self
+    }
+
+    pub fn arg_opt<S: Into<String>, V: IntoValue>(self, key: S, value: Option<V>) -> Self {
+        self.tx.queue().args.arg_opt(key, value);
+        self
+    }
+
+    pub fn arg_array<S: Into<String>, V: IntoValue>(self, key: S, values: impl IntoIterator<Item = V>) -> Self {
+        self.tx.queue().args.arg_array(key, values);
+        self
+    }
+
+    pub fn sub_array<S: Into<String>>(self, key: S, f: impl FnOnce(&mut ArrayBuilder)) -> Self {
+        let mut sub_array = ArrayBuilder::default();
+        f(&mut sub_array);
+        self.tx.queue().args.arg(key, sub_array);
+        self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::{collections::BTreeMap, sync::atomic::{AtomicU32, Ordering}, time::Duration};
+
+    use surrealdb::sql::Value;
+
+    use super::*;
+    use crate::{QueryMiddleware, RetryPolicy};
+
+    #[tokio::test]
+    async fn failed_statement_rolls_back() {
+        let db = Db::memory("test", "rollback").await.unwrap();
+        db.query("CREATE user:a").execute_check().await.unwrap();
+        let tx = db.begin();
+        tx.query("CREATE user:b");
+        tx.query("CREATE user:a");
+        tx.query("CREATE user:c");
+        let err = tx.commit().await.unwrap_err();
+        assert!(matches!(&err, Error::Query { sql, index: 1, .. } if sql == "CREATE user:a"));
+        assert!(matches!(err.root(), Error::Surrealdb(surrealdb::Error::RecordExists { .. })));
+        assert_eq!(db.count("user", None).await.unwrap(), 1);
+    }
+
+    /// Fails the first `n` queries with a conflict, as a concurrent writer would.
+    struct Clash(AtomicU32);
+
+    impl QueryMiddleware for Clash {
+        fn before(&self, _sql: &mut String, _vars: &mut BTreeMap<String, Value>) -> Result<()> {
+            match self.0.fetch_update(Ordering::SeqCst, Ordering::SeqCst, |n| n.checked_sub(1)) {
+                Ok(_) => Err(Error::Conflict(surrealdb::Error::QueryNotExecuted)),
+                Err(_) => Ok(()),
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn conflicts_are_retried() {
+        let policy = RetryPolicy::new(3).backoff(Duration::ZERO, Duration::ZERO);
+        let db = Db::memory("test", "conflict").await.unwrap().retry_policy(policy).middleware(Clash(AtomicU32::new(2)));
+        let tx = db.begin();
+        tx.query("CREATE user:a");
+        assert_eq!(tx.commit().await.unwrap().len(), 1);
+        let db = Db::memory("test", "conflict").await.unwrap().retry_policy(policy).middleware(Clash(AtomicU32::new(3)));
+        let tx = db.begin();
+        tx.query("CREATE user:b");
+        assert!(matches!(tx.commit().await, Err(Error::Conflict(_))));
+        assert!(db.select(crate::RecordId::new("user", "b")).await.unwrap().is_none());
+    }
+}
@@ -0,0 +1,76 @@
+use thiserror::Error;
+
+use crate::{Record, ValueKind};
+
+#[derive(Clone, Debug, Eq, Error, PartialEq)]
+pub enum FieldError {
+    #[error("missing field '{0}'")]
+    Missing(String),
+    #[error("field '{field}' should be {expected} but is {found}")]
+    WrongKind {
+        field: String,
+        expected: ValueKind,
+        found: ValueKind,
+    },
+}
+
+impl FieldError {
+    pub fn field(&self) -> &str {
+        match self {
+            Self::Missing(field) => field,
+            Self::WrongKind { field, .. } => field,
+        }
+    }
+}
+
+#[derive(Clone, Debug)]
+struct FieldRule {
+    name: String,
+    kind: ValueKind,
+    required: bool,
+}
+
+/// Declares the fields a record is expected to have. Optional fields may be absent, NONE
+/// or NULL; when present they must still match their declared kind.
+#[derive(Clone, Debug, Default)]
+pub struct RecordSchema(Vec<FieldRule>);
+
+impl RecordSchema {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn required<S: Into<String>>(mut self, name: S, kind: ValueKind) -> Self {
+        self.0.push(FieldRule { name: name.into(), kind, required: true });
+        self
+    }
+
+    pub fn optional<S: Into<String>>(mut self, name: S, kind: ValueKind) -> Self {
+        self.0.push(FieldRule { name: name.into(), kind, required: false });
+        self
+    }
+
+    pub fn validate(&self, record: &Record) -> Result<(), Vec<FieldError>> {
+        let mut errors = Vec::new();
+        for rule in &self.0 {
+            let found = record.0.get(&rule.name).map(ValueKind::of).unwrap_or(ValueKind::None);
+            if found == rule.kind {
+                continue;
+            }
+            let absent = matches!(found, ValueKind::None|ValueKind::Null);
+            if !absent {
+                errors.push(FieldError::WrongKind {
+                    field: rule.name.clone(),
+                    expected: rule.kind,
+                    found,
+                });
+            } else if rule.required {
+                errors.push(FieldError::Missing(rule.name.clone()));
+            }
+        }
+        match errors.is_empty() {
+            true => Ok(()),
+            false => Err(errors),
+        }
+    }
+}